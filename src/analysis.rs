@@ -23,167 +23,769 @@
  *                                                                           *
  * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * */
 
-use std::cell::RefCell;
-use std::collections::HashMap;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::cell::{Ref, RefCell};
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader};
 use std::path::{Path, PathBuf};
-use std::sync::LazyLock;
+use std::rc::Rc;
+use std::time::Duration;
 
 use anyhow::Result;
-use regex::{Captures, Regex};
 
+use crate::compression;
 use crate::config::Config;
+use crate::event::Event;
 use crate::log;
 use crate::output::Output;
-use crate::summary::{Summary, show_table};
-
-pub fn run<P>(input: P, config: Config) -> Result<()>
+use crate::parser::{self, Call};
+use crate::process::{
+    Process, Tree, command_line, is_program, show_programs, show_tree,
+};
+#[cfg(feature = "json")]
+use crate::summary::show_json;
+use crate::summary::{Summary, aggregate, show_delimited_header, show_table};
+use crate::syscall::Syscall;
+
+/// Analyzes a trace and prints the report in the configured output format.
+///
+/// # Errors
+///
+/// Returns an error if a trace can't be read or the report can't be written.
+pub fn run<P>(input: P, config: &Config) -> Result<()>
 where
     P: AsRef<Path> + Copy,
 {
-    let mut analysis = Analysis::new(config);
+    let mut analysis = Analysis::new(config.clone());
+
+    // strace -ff appends the PID to the output file name
+    analysis.pid = compression::split_suffix(input.as_ref())
+        .0
+        .extension()
+        .and_then(|extension| extension.to_str()?.parse().ok());
+
+    let separator = if config.output == Output::Tsv {
+        '\t'
+    } else {
+        ','
+    };
+
+    // without aggregation, continuous and delimited output is streamed
+    if !config.aggregate && config.pid.is_none() && config.program.is_none() {
+        match config.output {
+            Output::Continuous => {
+                return analysis
+                    .analyze(input, |summary| summary.show(config));
+            }
+
+            Output::Csv | Output::Tsv => {
+                show_delimited_header(separator);
+
+                return analysis.analyze(input, |summary| {
+                    summary.show_delimited(config, separator);
+                });
+            }
+
+            _ => {}
+        }
+    }
+
+    let summaries = analysis.summaries(input)?;
+
+    report(summaries, &analysis.tree.borrow(), config)
+}
+
+/// Reports the summaries in the configured output format.
+pub(crate) fn report(
+    mut summaries: Vec<Summary>,
+    tree: &Tree,
+    config: &Config,
+) -> Result<()> {
+    if let Some(pid) = config.pid {
+        summaries.retain(|summary| tree.descends(summary.pid, pid));
+    }
+
+    if let Some(name) = &config.program {
+        summaries.retain(|summary| {
+            summary
+                .program
+                .as_deref()
+                .is_some_and(|program| is_program(program, name))
+        });
+    }
+
+    // the tree and the programs aggregate per process and per program
+    if config.aggregate
+        && !matches!(config.output, Output::Tree | Output::Programs)
+    {
+        summaries = aggregate(summaries);
+    }
 
     match config.output {
         Output::Continuous => {
-            analysis.analyze(input, |summary| summary.show(config))
+            for summary in summaries {
+                summary.show(config);
+            }
         }
 
-        Output::Table => {
-            let summaries = RefCell::new(vec![]);
+        Output::Csv | Output::Tsv => {
+            let separator = if config.output == Output::Tsv {
+                '\t'
+            } else {
+                ','
+            };
 
-            analysis.analyze(input, |summary| {
-                summaries.borrow_mut().push(summary);
-            })?;
+            show_delimited_header(separator);
+
+            for summary in summaries {
+                summary.show_delimited(config, separator);
+            }
+        }
 
-            show_table(&summaries.into_inner(), config);
+        Output::Table => {
+            show_table(&summaries, config);
 
-            Ok(())
+            if config.histogram {
+                for summary in &summaries {
+                    summary.show_histogram(config);
+                }
+            }
         }
+
+        #[cfg(feature = "json")]
+        Output::Json => show_json(&summaries, config)?,
+
+        Output::Tree => show_tree(summaries, tree, config),
+
+        Output::Programs => show_programs(summaries, config),
     }
+
+    Ok(())
 }
 
+/// A file descriptor table, which threads share with their process.
+type Fds = Rc<RefCell<HashMap<u32, FdEntry>>>;
+
+/// An open file descriptor.
 #[derive(Clone)]
-struct Analysis {
-    fds: HashMap<u32, Summary>,
+struct FdEntry {
+    summary: Summary,
+
+    /// Whether the file descriptor is closed by a successful exec.
+    cloexec: bool,
+}
+
+impl FdEntry {
+    const fn new(summary: Summary, cloexec: bool) -> Self {
+        Self { summary, cloexec }
+    }
+}
+
+/// The state of the analysis of a single process, mainly its file descriptor
+/// table.
+pub struct Analysis {
+    pid: Option<u32>,
+    fds: Fds,
+
+    /// Whether the file descriptor table is shared with the parent process,
+    /// which reports the file descriptors that are still open at the end.
+    shared: bool,
+
+    /// Tables that this process has unshared, but still reports, because
+    /// the processes it has shared them with don't, see [`Self::unshare`].
+    unshared: Vec<Fds>,
+
+    /// The processes of the trace, shared by all of them.
+    tree: Rc<RefCell<Tree>>,
+
     config: Config,
+    timestamp: Option<Duration>,
+
+    /// Traffic of sockets that are not connected, keyed by peer address.
+    peers: HashMap<String, Summary>,
+
+    /// Failed opens, keyed by path.
+    failed: HashMap<String, Summary>,
+
+    /// The unfinished syscall of this process, with its timestamp.
+    pending: Option<(Option<Duration>, String)>,
 }
 
 impl Analysis {
-    fn new(config: Config) -> Self {
+    /// Returns the analysis of a process that starts out with the standard
+    /// streams as its open file descriptors.
+    #[must_use]
+    pub fn new(config: Config) -> Self {
         let stdin = Summary::new("STDIN");
         let stdout = Summary::new("STDOUT");
         let stderr = Summary::new("STDERR");
 
-        let mut fds: HashMap<u32, Summary> = HashMap::new();
+        let mut fds = HashMap::new();
+
+        fds.insert(0, FdEntry::new(stdin, false));
+        fds.insert(1, FdEntry::new(stdout, false));
+        fds.insert(2, FdEntry::new(stderr, false));
+
+        Self {
+            pid: None,
+            fds: Rc::new(RefCell::new(fds)),
+            shared: false,
+            unshared: vec![],
+            tree: Rc::new(RefCell::new(Tree::default())),
+            config,
+            timestamp: None,
+            peers: HashMap::new(),
+            failed: HashMap::new(),
+            pending: None,
+        }
+    }
+
+    /// Returns the analysis of the running process `pid` that starts out
+    /// with the file descriptors it has currently open, as far as they can
+    /// be read from `/proc`.
+    pub(crate) fn running(pid: u32, config: Config) -> Self {
+        let analysis = Self {
+            pid: Some(pid),
+            ..Self::new(config)
+        };
+
+        let entries = match fs::read_dir(format!("/proc/{pid}/fd")) {
+            Ok(entries) => entries,
+            Err(error) => {
+                analysis.warn(format!(
+                    "can't read the open file descriptors of {pid}: {error}"
+                ));
 
-        fds.insert(0, stdin);
-        fds.insert(1, stdout);
-        fds.insert(2, stderr);
+                return analysis;
+            }
+        };
+
+        for entry in entries.flatten() {
+            let fd = entry.file_name().to_str().and_then(|fd| fd.parse().ok());
+
+            // the standard streams keep their names
+            let Some(fd) = fd.filter(|fd| *fd > 2) else {
+                continue;
+            };
+
+            let Ok(target) = fs::read_link(entry.path()) else {
+                continue;
+            };
+
+            let target = target.to_string_lossy();
+
+            let summary = if target.starts_with("socket:") {
+                Summary::socket(None)
+            } else if target.starts_with("pipe:") {
+                Summary::pipe()
+            } else {
+                Summary::new(&target)
+            };
+
+            analysis.debug(format!("[attach] {fd} => {}", summary.file));
+            analysis
+                .fds
+                .borrow_mut()
+                .insert(fd, FdEntry::new(summary, false));
+        }
 
-        Self { fds, config }
+        analysis
     }
 
-    fn analyze<F, P>(&mut self, input: P, f: F) -> Result<()>
+    /// Analyzes the trace in the `input` file and calls `f` for every
+    /// [`Summary`], i.e. whenever a file descriptor is closed and, at the
+    /// end, for every file descriptor that is still open.
+    ///
+    /// Traces of child processes created with `strace -ff` are followed.
+    /// Traces compressed with gzip, xz or zstd are decompressed. If `input`
+    /// is `-`, the trace is read from stdin. Traces from stdin or a
+    /// pipe are analyzed as they arrive, child traces can't be followed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a trace can't be read.
+    pub fn analyze<F, P>(&mut self, input: P, f: F) -> Result<()>
     where
         F: Fn(Summary) + Copy,
         P: AsRef<Path> + Copy,
     {
+        let input = input.as_ref();
+
+        if input == Path::new("-") {
+            let reader = compression::decode(io::stdin().lock())?;
+            return self.analyze_lines(reader, None, f);
+        }
+
         let file = File::open(input)?;
 
-        for line in BufReader::new(file).lines() {
-            let line = line?;
+        // strace -ff creates the child traces next to regular files only
+        let follow = file.metadata()?.is_file().then_some(input);
+
+        let reader = compression::decode(BufReader::new(file))?;
+        self.analyze_lines(reader, follow, f)
+    }
+
+    /// Analyzes a trace that is read from a stream, e.g. stdin or a pipe,
+    /// and calls `f` for every [`Summary`]. Lines are analyzed as they
+    /// arrive. Traces of child processes created with `strace -ff` can't be
+    /// followed, only traces created with `strace -f` are complete.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the trace can't be read.
+    pub fn analyze_reader<F, R>(&mut self, reader: R, f: F) -> Result<()>
+    where
+        F: Fn(Summary) + Copy,
+        R: BufRead,
+    {
+        self.analyze_lines(reader, None, f)
+    }
+
+    fn analyze_lines<F, R>(
+        &mut self,
+        reader: R,
+        input: Option<&Path>,
+        f: F,
+    ) -> Result<()>
+    where
+        F: Fn(Summary) + Copy,
+        R: BufRead,
+    {
+        let mut trace = Trace::new(self, input);
+
+        for line in reader.lines() {
+            trace.analyze(&line?, f)?;
+        }
+
+        trace.flush(f);
+
+        Ok(())
+    }
+
+    /// Returns the processes that have been encountered in the trace so
+    /// far, ordered by PID.
+    #[must_use]
+    pub fn processes(&self) -> Vec<Process> {
+        self.tree.borrow().processes().cloned().collect()
+    }
+
+    /// Analyzes the trace in the `input` file and returns all summaries.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a trace can't be read.
+    pub fn summaries<P>(&mut self, input: P) -> Result<Vec<Summary>>
+    where
+        P: AsRef<Path> + Copy,
+    {
+        let summaries = RefCell::new(vec![]);
+
+        self.analyze(input, |summary| {
+            summaries.borrow_mut().push(summary);
+        })?;
+
+        Ok(summaries.into_inner())
+    }
+
+    /// Joins syscalls that strace split into an unfinished and a resumed
+    /// line, because another process was scheduled in between.
+    fn resume(&mut self, event: &Event) -> Resume {
+        if let Some(call) = event.call.strip_suffix(" <unfinished ...>") {
+            if let Some((_, pending)) = &self.pending {
+                self.verbose(format!(
+                    "[resume] dropping unfinished {pending}"
+                ));
+            }
+
+            self.pending = Some((event.timestamp, String::from(call)));
+
+            return Resume::Unfinished;
+        }
+
+        let Some((syscall, rest)) = event
+            .call
+            .strip_prefix("<... ")
+            .and_then(|call| call.split_once(" resumed>"))
+        else {
+            return Resume::Complete;
+        };
+
+        match self.pending.take() {
+            Some((timestamp, mut call))
+                if call.starts_with(&format!("{syscall}(")) =>
+            {
+                call.push_str(rest.trim_start());
+                Resume::Resumed(timestamp, call)
+            }
+
+            Some((_, call)) => {
+                self.verbose(format!(
+                    "[resume] {syscall} doesn't resume unfinished {call}"
+                ));
+
+                Resume::Unfinished
+            }
+
+            None => {
+                self.verbose(format!("[resume] no unfinished {syscall}"));
+                Resume::Unfinished
+            }
+        }
+    }
+
+    /// Dispatches a single event to its syscall handler, returns the PID of
+    /// the child process if the event created one.
+    // ALLOW: one arm per syscall reads better than splitting it up
+    #[allow(clippy::too_many_lines)]
+    fn dispatch<F>(&mut self, event: &Event, f: F) -> Option<(u32, bool)>
+    where
+        F: Fn(Summary) + Copy,
+    {
+        self.timestamp = event.timestamp;
+
+        let call = Call::parse(event.call)?;
+        let syscall = Syscall::parse(&call)?;
+
+        let name = call.name;
+        let duration = event.duration;
+
+        match syscall {
+            Syscall::Accept {
+                listen,
+                address,
+                fd,
+                cloexec,
+            } => self.syscall_accept(name, listen, address, fd, cloexec, f),
+
+            Syscall::Bind { fd, address }
+            | Syscall::Connect { fd, address }
+            | Syscall::GetPeerName { fd, address } => {
+                self.name_socket(name, fd, address);
+            }
+
+            Syscall::Clone {
+                child,
+                shared_files,
+            } => {
+                return Some(self.syscall_clone(child, shared_files));
+            }
 
-            for cap in RE_CREAT.captures_iter(&line) {
-                self.syscall_creat(&cap, f);
+            Syscall::Close { fd, error } => self.syscall_close(fd, error, f),
+
+            Syscall::CloseRange {
+                first,
+                last,
+                cloexec,
+                unshare,
+            } => self.syscall_close_range(first, last, cloexec, unshare, f),
+
+            Syscall::Dup {
+                oldfd,
+                newfd,
+                cloexec,
+            } => self.dup(name, oldfd, newfd, cloexec, f),
+
+            Syscall::Exec {
+                dirfd,
+                program,
+                args,
+            } => self.syscall_exec(dirfd, &program, args, f),
+
+            Syscall::ExecFailed {
+                dirfd,
+                program,
+                error,
+            } => {
+                self.syscall_path_failed(name, dirfd, &program, error);
             }
 
-            for cap in RE_CLOSE.captures_iter(&line) {
-                self.syscall_close(&cap, f);
+            Syscall::Open {
+                dirfd,
+                path,
+                fd,
+                cloexec,
+            } => {
+                self.syscall_open(name, dirfd, &path, fd, cloexec, f);
             }
 
-            for cap in RE_CLONE.captures_iter(&line) {
-                self.syscall_clone(&cap, input, f)?;
+            Syscall::OpenFailed { dirfd, path, error } => {
+                self.syscall_path_failed(name, dirfd, &path, error);
             }
 
-            for cap in RE_DUP.captures_iter(&line) {
-                self.syscall_dup(&cap, f);
+            Syscall::Pipe {
+                read,
+                write,
+                cloexec,
+            } => self.syscall_pipe(read, write, cloexec, f),
+
+            Syscall::Read { fd, size, bytes } => {
+                self.syscall_read(name, fd, size, bytes, duration);
             }
 
-            for cap in RE_DUP2.captures_iter(&line) {
-                self.syscall_dup2(&cap, f);
+            Syscall::ReadFailed { fd, error }
+            | Syscall::WriteFailed { fd, error } => {
+                self.syscall_failed(name, fd, error);
             }
 
-            for cap in RE_FCNTL_DUP.captures_iter(&line) {
-                self.syscall_fcntl_dup(&cap, f);
+            Syscall::Recv {
+                fd,
+                size,
+                bytes,
+                address,
+            } => {
+                let read = |summary: &mut Summary| {
+                    summary.update_read(size, bytes, duration);
+                };
+
+                if !self.update_socket(fd, address, read) {
+                    self.verbose(format!("[{name}] unknown fd {fd}"));
+                }
             }
 
-            for cap in RE_OPEN.captures_iter(&line) {
-                self.syscall_open(&cap, f);
+            Syscall::Send {
+                fd,
+                size,
+                bytes,
+                address,
+            } => {
+                let write = |summary: &mut Summary| {
+                    summary.update_write(size, bytes, duration);
+                };
+
+                if !self.update_socket(fd, address, write) {
+                    self.verbose(format!("[{name}] unknown fd {fd}"));
+                }
             }
 
-            for cap in RE_OPENAT.captures_iter(&line) {
-                self.syscall_openat(&cap, f);
+            Syscall::SetCloexec { fd, cloexec } => {
+                self.syscall_set_cloexec(name, fd, cloexec);
             }
 
-            for cap in RE_PIPE.captures_iter(&line) {
-                self.syscall_pipe(&cap, f);
+            Syscall::Socket {
+                domain,
+                kind,
+                fd,
+                cloexec,
+            } => {
+                let socket = (domain, kind, cloexec);
+                self.syscall_socket(socket, fd, f);
             }
 
-            for cap in RE_PREAD.captures_iter(&line) {
-                self.syscall_pread(&cap);
+            Syscall::SocketPair {
+                domain,
+                kind,
+                first,
+                second,
+                cloexec,
+            } => {
+                let socket = (domain, kind, cloexec);
+                self.syscall_socketpair(socket, first, second, f);
             }
 
-            for cap in RE_PWRITE.captures_iter(&line) {
-                self.syscall_pwrite(&cap);
+            Syscall::Transfer {
+                from,
+                to,
+                size,
+                bytes,
+            } => {
+                self.syscall_transfer(name, from, to, size, bytes, duration);
             }
 
-            for cap in RE_READ.captures_iter(&line) {
-                self.syscall_read(&cap);
+            Syscall::Write { fd, size, bytes } => {
+                self.syscall_write(name, fd, size, bytes, duration);
             }
+        }
+
+        None
+    }
+
+    /// Passes a summary on to the output, labeled with this process and the
+    /// program it is executing.
+    fn emit<F>(&self, summary: Summary, f: F)
+    where
+        F: Fn(Summary) + Copy,
+    {
+        let program = self
+            .tree
+            .borrow()
+            .get(self.pid)
+            .and_then(|process| process.program.clone());
+
+        f(Summary {
+            pid: self.pid,
+            program,
+            ..summary
+        });
+    }
 
-            for cap in RE_SOCKET.captures_iter(&line) {
-                self.syscall_socket(&cap, f);
+    fn flush<F>(&self, f: F)
+    where
+        F: Fn(Summary) + Copy,
+    {
+        // a shared table is reported by the process it is shared with
+        if !self.shared {
+            for entry in self.fds.borrow().values() {
+                self.emit(entry.summary.clone(), f);
             }
+        }
 
-            for cap in RE_WRITE.captures_iter(&line) {
-                self.syscall_write(&cap);
+        for fds in &self.unshared {
+            for entry in fds.borrow().values() {
+                self.emit(entry.summary.clone(), f);
             }
         }
 
-        for summary in self.fds.values() {
-            f(summary.clone());
+        for summary in self.peers.values().chain(self.failed.values()) {
+            self.emit(summary.clone(), f);
         }
+    }
 
-        Ok(())
+    /// Returns the analysis of the child process `pid`. With `CLONE_FILES`,
+    /// e.g. for threads, the child shares the file descriptor table,
+    /// otherwise it gets a copy with zeroed counters.
+    fn fork(&self, pid: u32, shared_files: bool) -> Self {
+        let fds = if shared_files {
+            Rc::clone(&self.fds)
+        } else {
+            let mut fds = self.fds.borrow().clone();
+
+            for entry in fds.values_mut() {
+                entry.summary.reset();
+            }
+
+            Rc::new(RefCell::new(fds))
+        };
+
+        self.tree.borrow_mut().spawn(self.pid, pid);
+
+        Self {
+            pid: Some(pid),
+            fds,
+            shared: shared_files,
+            unshared: vec![],
+            tree: Rc::clone(&self.tree),
+            config: self.config.clone(),
+            timestamp: self.timestamp,
+            peers: HashMap::new(),
+            failed: HashMap::new(),
+            pending: None,
+        }
+    }
+
+    /// Takes over the file descriptor table of the `forked` child, in case
+    /// the child process was encountered in the trace before its parent's
+    /// clone syscall returned. Only the file descriptors the child has
+    /// opened in the meantime are kept, the ones of the parent they replace
+    /// are reported.
+    fn inherit<F>(&mut self, forked: Self, f: F)
+    where
+        F: Fn(Summary) + Copy,
+    {
+        let own = self.fds.take();
+
+        self.fds = forked.fds;
+        self.shared = forked.shared;
+
+        for (fd, FdEntry { summary, cloexec }) in own {
+            let merged = self
+                .fds
+                .borrow_mut()
+                .get_mut(&fd)
+                .filter(|entry| entry.summary.file == summary.file)
+                .map(|entry| {
+                    entry.summary.merge(&summary);
+                    entry.cloexec = cloexec;
+                })
+                .is_some();
+
+            if !merged {
+                self.insert(fd, FdEntry::new(summary, cloexec), "clone", f);
+            }
+        }
+    }
+
+    /// Returns whether the process waits for a `clone`, `clone3`, `fork` or
+    /// `vfork` to return, and if so, whether the child shares the file
+    /// descriptor table.
+    fn pending_clone(&self) -> Option<bool> {
+        let (_, call) = self.pending.as_ref()?;
+        let (name, args) = call.split_once('(')?;
+
+        match name {
+            "clone" | "clone3" => Some(
+                args.split(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                    .any(|flag| flag == "CLONE_FILES"),
+            ),
+
+            "fork" | "vfork" => Some(false),
+
+            _ => None,
+        }
+    }
+
+    /// Gives the process its own copy of a shared file descriptor table,
+    /// e.g. with `CLOSE_RANGE_UNSHARE`.
+    fn unshare(&mut self) {
+        if Rc::strong_count(&self.fds) == 1 {
+            return;
+        }
+
+        self.debug("[unshare] file descriptors");
+
+        let mut fds = self.fds.borrow().clone();
+
+        for entry in fds.values_mut() {
+            entry.summary.reset();
+        }
+
+        let fds = Rc::new(RefCell::new(fds));
+
+        // the processes left with the table don't report it, so it is still
+        // reported by this one, including their I/O from now on
+        let old = std::mem::replace(&mut self.fds, fds);
+
+        if !self.shared {
+            self.unshared.push(old);
+        }
+
+        self.shared = false;
     }
 
-    fn insert<F>(&mut self, fd: u32, summary: Summary, syscall: &str, f: F)
+    fn insert<F>(&self, fd: u32, entry: FdEntry, syscall: &str, f: F)
     where
         F: Fn(Summary) + Copy,
     {
-        if let Some(summary) = self.fds.insert(fd, summary) {
+        let replaced = self.fds.borrow_mut().insert(fd, entry);
+
+        if let Some(FdEntry { summary, .. }) = replaced {
             self.debug(format!(
                 "[{syscall}] dropping {} without explicit close",
                 summary.file
             ));
 
-            f(summary);
+            self.emit(summary, f);
         }
     }
 
-    fn dup<F>(&mut self, syscall: &str, oldfd: u32, newfd: u32, f: F)
-    where
+    fn dup<F>(
+        &self,
+        syscall: &str,
+        oldfd: u32,
+        newfd: u32,
+        cloexec: bool,
+        f: F,
+    ) where
         F: Fn(Summary) + Copy,
     {
-        let summary = self.fds.get(&oldfd).map_or_else(
+        let summary = self.fds.borrow().get(&oldfd).map_or_else(
             || {
                 self.debug(format!("[{syscall}] couldn't find oldfd {oldfd}"));
 
                 Summary::new("DUP")
             },
-            |summary_old| {
-                let old_file = &summary_old.file;
+            |entry_old| {
+                let old_file = &entry_old.summary.file;
 
                 self.debug(format!(
                     "[{syscall}] {oldfd} -> {newfd} => {old_file}"
@@ -193,316 +795,766 @@ impl Analysis {
             },
         );
 
-        self.insert(newfd, summary, syscall, f);
+        self.insert(newfd, FdEntry::new(summary, cloexec), syscall, f);
+    }
+
+    fn syscall_accept<F>(
+        &self,
+        syscall: &str,
+        listen_fd: u32,
+        address: Option<&str>,
+        fd: u32,
+        cloexec: bool,
+        f: F,
+    ) where
+        F: Fn(Summary) + Copy,
+    {
+        let mut summary = self.fds.borrow().get(&listen_fd).map_or_else(
+            || {
+                self.verbose(format!("[{syscall}] unknown fd {listen_fd}"));
+                Summary::socket(None)
+            },
+            |listener| {
+                let mut summary = listener.summary.clone();
+                summary.reset();
+                summary
+            },
+        );
+
+        if let Some(name) =
+            address.and_then(|address| socket_name(&summary, address))
+        {
+            summary.file = name;
+        }
+
+        self.debug(format!("[{syscall}] {fd} => {}", summary.file));
+
+        self.insert(fd, FdEntry::new(summary, cloexec), syscall, f);
+    }
+
+    fn name_socket(&self, syscall: &str, fd: u32, address: &str) {
+        let mut fds = self.fds.borrow_mut();
+
+        let Some(FdEntry { summary, .. }) = fds.get_mut(&fd) else {
+            self.verbose(format!("[{syscall}] unknown fd {fd}"));
+            return;
+        };
+
+        if let Some(name) = socket_name(summary, address) {
+            summary.file.clone_from(&name);
+            self.debug(format!("[{syscall}] {fd} => {name}"));
+        }
+    }
+
+    fn syscall_clone(&self, pid: u32, shared_files: bool) -> (u32, bool) {
+        if shared_files {
+            self.debug(format!("[clone] {pid} sharing file descriptors"));
+        } else {
+            self.debug(format!("[clone] {pid}"));
+        }
+
+        (pid, shared_files)
     }
 
-    fn syscall_clone<F, P>(&self, cap: &Captures, input: P, f: F) -> Result<()>
+    fn follow<F, P>(
+        &self,
+        pid: u32,
+        shared_files: bool,
+        input: P,
+        f: F,
+    ) -> Result<()>
     where
         F: Fn(Summary) + Copy,
         P: AsRef<Path>,
     {
-        let pid = &cap[1];
+        let trace = compression::child_trace(input.as_ref(), pid);
+
+        if !trace.is_file() {
+            self.warn(format!(
+                "[clone] trace of pid {pid} is missing, expected {}",
+                trace.display()
+            ));
 
-        let trace = input.as_ref().with_extension(pid);
+            return Ok(());
+        }
 
         self.verbose(format!(
             "[clone] tracing pid {pid} in {} ...",
             trace.display()
         ));
 
-        let mut cloned_fds = self.fds.clone();
-
-        for summary in cloned_fds.values_mut() {
-            summary.reset();
-        }
-
-        self.clone().analyze(&trace, f)?;
+        let mut child = self.fork(pid, shared_files);
+        child.analyze(&trace, f)?;
 
         self.verbose(format!("[clone] tracing pid {pid} finished"));
 
         Ok(())
     }
 
-    fn syscall_close<F>(&mut self, cap: &Captures, f: F)
+    fn syscall_close<F>(&self, fd: u32, error: Option<&str>, f: F)
     where
         F: Fn(Summary) + Copy,
     {
-        let fd: u32 = cap[1].parse().unwrap();
-        let status: i32 = cap[2].parse().unwrap();
-        let error = &cap[3];
         let syscall = "close";
 
-        match (status, error) {
-            (0, _) => {
+        match error {
+            None => {
                 self.finish(fd, syscall, f);
             }
 
-            (_, "EBADF") => {
+            Some("EBADF") => {
                 self.debug(format!("[close] {fd} => bad fd"));
             }
 
-            (_, error) => {
+            Some(error) => {
                 self.verbose(format!("[close] {fd} => {error}"));
                 self.finish(fd, syscall, f);
             }
         }
     }
 
-    fn syscall_creat<F>(&mut self, cap: &Captures, f: F)
-    where
+    /// Closes all known file descriptors from `first` to `last`, or marks
+    /// them close-on-exec with `CLOSE_RANGE_CLOEXEC`.
+    fn syscall_close_range<F>(
+        &mut self,
+        first: u32,
+        last: u32,
+        cloexec: bool,
+        unshare: bool,
+        f: F,
+    ) where
         F: Fn(Summary) + Copy,
     {
-        let file = &cap[1];
-        let fd: u32 = cap[2].parse().unwrap();
+        let syscall = "close_range";
 
-        self.debug(format!("[creat] {fd} => {file}"));
+        if unshare {
+            self.unshare();
+        }
 
-        let syscall = "creat";
-        self.insert(fd, Summary::new(file), syscall, f);
-    }
+        if cloexec {
+            self.debug(format!("[{syscall}] {first}..={last} close-on-exec"));
 
-    fn syscall_dup<F>(&mut self, cap: &Captures, f: F)
-    where
-        F: Fn(Summary) + Copy,
-    {
-        let oldfd: u32 = cap[1].parse().unwrap();
-        let newfd: u32 = cap[2].parse().unwrap();
+            for (_, entry) in self
+                .fds
+                .borrow_mut()
+                .iter_mut()
+                .filter(|(fd, _)| (first..=last).contains(*fd))
+            {
+                entry.cloexec = true;
+            }
 
-        self.dup("dup", oldfd, newfd, f);
-    }
+            return;
+        }
 
-    fn syscall_dup2<F>(&mut self, cap: &Captures, f: F)
-    where
-        F: Fn(Summary) + Copy,
-    {
-        let oldfd: u32 = cap[1].parse().unwrap();
-        let newfd: u32 = cap[2].parse().unwrap();
+        let mut fds: Vec<u32> = self
+            .fds
+            .borrow()
+            .keys()
+            .copied()
+            .filter(|fd| (first..=last).contains(fd))
+            .collect();
 
-        self.dup("dup2", oldfd, newfd, f);
+        fds.sort_unstable();
+
+        for fd in fds {
+            self.finish(fd, syscall, f);
+        }
     }
 
-    fn syscall_fcntl_dup<F>(&mut self, cap: &Captures, f: F)
-    where
+    fn syscall_open<F>(
+        &self,
+        syscall: &str,
+        dirfd: Option<u32>,
+        pathname: &str,
+        fd: u32,
+        cloexec: bool,
+        f: F,
+    ) where
         F: Fn(Summary) + Copy,
     {
-        let oldfd: u32 = cap[1].parse().unwrap();
-        let newfd: u32 = cap[2].parse().unwrap();
+        let file = self.join_paths(dirfd, pathname);
 
-        self.dup("fcntl-dup", oldfd, newfd, f);
+        self.debug(format!("[{syscall}] {fd} => {file}"));
+
+        let summary = Summary {
+            opens: 1,
+            ..Summary::new(&file)
+        };
+
+        self.insert(fd, FdEntry::new(summary, cloexec), syscall, f);
     }
 
-    fn syscall_open<F>(&mut self, cap: &Captures, f: F)
-    where
-        F: Fn(Summary) + Copy,
-    {
-        let file = &cap[1];
-        let fd: u32 = cap[2].parse().unwrap();
+    /// Records failed syscalls on paths, e.g. opens of missing files or a
+    /// shell looking up a program in its `PATH`.
+    fn syscall_path_failed(
+        &mut self,
+        syscall: &str,
+        dirfd: Option<u32>,
+        pathname: &str,
+        error: &str,
+    ) {
+        let file = self.join_paths(dirfd, pathname);
 
-        self.debug(format!("[open] {fd} => {file}"));
+        self.debug(format!("[{syscall}] {file} => {error}"));
 
-        let syscall = "open";
-        self.insert(fd, Summary::new(file), syscall, f);
+        self.failed
+            .entry(file)
+            .or_insert_with_key(|file| Summary::new(file))
+            .update_error(syscall, error);
     }
 
-    fn syscall_openat<F>(&mut self, cap: &Captures, f: F)
-    where
+    /// Records the program that the process executes. The file descriptors
+    /// marked close-on-exec are closed, the I/O so far is reported for the
+    /// old program and the files that stay open start over with zeroed
+    /// counters for the new one.
+    fn syscall_exec<F>(
+        &mut self,
+        dirfd: Option<u32>,
+        program: &str,
+        args: Vec<String>,
+        f: F,
+    ) where
         F: Fn(Summary) + Copy,
     {
-        let dirfd = &cap[1];
-        let pathname = &cap[2];
-        let fd: u32 = cap[3].parse().unwrap();
+        let program = self.join_paths(dirfd, program);
 
-        let file = self.join_paths(dirfd, pathname);
+        self.debug(format!("[exec] {}", command_line(&program, &args)));
 
-        self.debug(format!("[openat] {fd} => {file}"));
+        let mut cloexec: Vec<u32> = self
+            .fds
+            .borrow()
+            .iter()
+            .filter(|(_, entry)| entry.cloexec)
+            .map(|(fd, _)| *fd)
+            .collect();
 
-        let syscall = "openat";
-        self.insert(fd, Summary::new(&file), syscall, f);
-    }
+        cloexec.sort_unstable();
 
-    fn syscall_pipe<F>(&mut self, cap: &Captures, f: F)
-    where
-        F: Fn(Summary) + Copy,
-    {
-        let readend = cap[1].parse().unwrap();
-        let writeend = cap[2].parse().unwrap();
+        for fd in cloexec {
+            self.finish(fd, "exec", f);
+        }
 
-        self.debug(format!("[pipe] {readend} => {writeend}"));
+        for fds in std::iter::once(&self.fds).chain(&self.unshared) {
+            for entry in fds.borrow_mut().values_mut() {
+                self.emit(entry.summary.clone(), f);
+                entry.summary.reset();
+            }
+        }
 
-        let syscall = "pipe";
-        self.insert(readend, Summary::pipe(), syscall, f);
-        self.insert(writeend, Summary::pipe(), syscall, f);
-    }
+        let done: Vec<_> = self
+            .peers
+            .drain()
+            .chain(self.failed.drain())
+            .map(|(_, summary)| summary)
+            .collect();
+
+        for summary in done {
+            self.emit(summary, f);
+        }
 
-    fn syscall_pread(&mut self, cap: &Captures) {
-        let fd: u32 = cap[1].parse().unwrap();
+        self.tree.borrow_mut().exec(self.pid, program, args);
+    }
 
+    /// Records failed reads and writes, e.g. `EAGAIN` on non-blocking
+    /// sockets.
+    fn syscall_failed(&self, syscall: &str, fd: u32, error: &str) {
         // ALLOW: false positive: self borrowed both mutable and immutable
         #[allow(clippy::option_if_let_else)]
-        if let Some(summary) = self.fds.get_mut(&fd) {
-            let opsize: u64 = cap[2].parse().unwrap();
-            let bytes: u64 = cap[3].parse().unwrap();
-            summary.update_read(opsize, bytes);
+        if let Some(FdEntry { summary, .. }) =
+            self.fds.borrow_mut().get_mut(&fd)
+        {
+            summary.update_error(syscall, error);
         } else {
-            self.verbose(format!("[pread] unknown fd {fd}"));
+            self.verbose(format!("[{syscall}] unknown fd {fd} => {error}"));
         }
     }
 
-    fn syscall_pwrite(&mut self, cap: &Captures) {
-        let fd: u32 = cap[1].parse().unwrap();
+    fn syscall_pipe<F>(&self, readend: u32, writeend: u32, cloexec: bool, f: F)
+    where
+        F: Fn(Summary) + Copy,
+    {
+        self.debug(format!("[pipe] {readend} => {writeend}"));
+
+        let syscall = "pipe";
+        let entry = FdEntry::new(Summary::pipe(), cloexec);
+        self.insert(readend, entry.clone(), syscall, f);
+        self.insert(writeend, entry, syscall, f);
+    }
 
+    fn syscall_set_cloexec(&self, syscall: &str, fd: u32, cloexec: bool) {
         // ALLOW: false positive: self borrowed both mutable and immutable
         #[allow(clippy::option_if_let_else)]
-        if let Some(summary) = self.fds.get_mut(&fd) {
-            let opsize: u64 = cap[2].parse().unwrap();
-            let bytes: u64 = cap[3].parse().unwrap();
-            summary.update_write(opsize, bytes);
+        if let Some(entry) = self.fds.borrow_mut().get_mut(&fd) {
+            entry.cloexec = cloexec;
         } else {
-            self.verbose(format!("[pwrite] unknown fd {fd}"));
+            self.verbose(format!("[{syscall}] unknown fd {fd}"));
         }
     }
 
-    fn syscall_read(&mut self, cap: &Captures) {
-        let fd: u32 = cap[1].parse().unwrap();
-
+    fn syscall_read(
+        &self,
+        syscall: &str,
+        fd: u32,
+        opsize: u64,
+        bytes: u64,
+        duration: Option<Duration>,
+    ) {
         // ALLOW: false positive: self borrowed both mutable and immutable
         #[allow(clippy::option_if_let_else)]
-        if let Some(summary) = self.fds.get_mut(&fd) {
-            let opsize: u64 = cap[2].parse().unwrap();
-            let bytes: u64 = cap[3].parse().unwrap();
-            summary.update_read(opsize, bytes);
+        if let Some(FdEntry { summary, .. }) =
+            self.fds.borrow_mut().get_mut(&fd)
+        {
+            summary.update_read(opsize, bytes, duration);
         } else {
-            self.verbose(format!("[read] unknown fd {fd}"));
+            self.verbose(format!("[{syscall}] unknown fd {fd}"));
         }
     }
 
-    fn syscall_socket<F>(&mut self, cap: &Captures, f: F)
+    /// Accounts socket traffic to the socket `fd`, or to the peer summary
+    /// if the syscall names another peer explicitly, e.g. with `sendto` on a
+    /// datagram socket that is not connected. Returns false if the socket
+    /// is unknown.
+    fn update_socket<U>(
+        &mut self,
+        fd: u32,
+        address: Option<&str>,
+        update: U,
+    ) -> bool
     where
+        U: FnOnce(&mut Summary),
+    {
+        let mut fds = self.fds.borrow_mut();
+
+        let Some(FdEntry {
+            summary: socket, ..
+        }) = fds.get_mut(&fd)
+        else {
+            return false;
+        };
+
+        // a connected socket may still be told its own peer, e.g. by
+        // recvfrom on a connected datagram socket
+        let Some(name) = address
+            .and_then(|address| socket_name(socket, address))
+            .filter(|name| *name != socket.file)
+        else {
+            update(socket);
+            return true;
+        };
+
+        let protocol = socket.protocol.clone();
+
+        update(self.peers.entry(name).or_insert_with_key(|name| Summary {
+            protocol,
+            ..Summary::new(name)
+        }));
+
+        true
+    }
+
+    fn syscall_socket<F>(
+        &self,
+        (domain, kind, cloexec): (&str, &str, bool),
+        fd: u32,
+        f: F,
+    ) where
         F: Fn(Summary) + Copy,
     {
-        let fd: u32 = cap[1].parse().unwrap();
+        let protocol = socket_protocol(domain, kind);
 
-        self.debug(format!("[socket] {fd}"));
+        self.debug(format!("[socket] {fd} => {protocol}"));
 
         let syscall = "socket";
-        self.insert(fd, Summary::socket(), syscall, f);
+        let entry = FdEntry::new(Summary::socket(Some(protocol)), cloexec);
+        self.insert(fd, entry, syscall, f);
     }
 
-    fn syscall_write(&mut self, cap: &Captures) {
-        let fd: u32 = cap[1].parse().unwrap();
+    fn syscall_socketpair<F>(
+        &self,
+        (domain, kind, cloexec): (&str, &str, bool),
+        first: u32,
+        second: u32,
+        f: F,
+    ) where
+        F: Fn(Summary) + Copy,
+    {
+        let protocol = socket_protocol(domain, kind);
+
+        self.debug(format!("[socketpair] {first}, {second} => {protocol}"));
+
+        let syscall = "socketpair";
+        let entry = FdEntry::new(Summary::socket(Some(protocol)), cloexec);
+        self.insert(first, entry.clone(), syscall, f);
+        self.insert(second, entry, syscall, f);
+    }
+
+    /// Handles zero-copy transfers between two file descriptors, i.e.
+    /// `copy_file_range`, `sendfile`, `splice` and `tee`.
+    fn syscall_transfer(
+        &self,
+        syscall: &str,
+        fd_in: u32,
+        fd_out: u32,
+        opsize: u64,
+        bytes: u64,
+        duration: Option<Duration>,
+    ) {
+        self.debug(format!("[{syscall}] {fd_in} -> {fd_out} => {bytes}"));
+
+        if let Some(FdEntry { summary, .. }) =
+            self.fds.borrow_mut().get_mut(&fd_in)
+        {
+            summary.update_read_zero_copy(opsize, bytes, duration);
+        } else {
+            self.verbose(format!("[{syscall}] unknown fd {fd_in}"));
+        }
+
+        if let Some(FdEntry { summary, .. }) =
+            self.fds.borrow_mut().get_mut(&fd_out)
+        {
+            summary.update_write_zero_copy(opsize, bytes, duration);
+        } else {
+            self.verbose(format!("[{syscall}] unknown fd {fd_out}"));
+        }
+    }
 
+    fn syscall_write(
+        &self,
+        syscall: &str,
+        fd: u32,
+        opsize: u64,
+        bytes: u64,
+        duration: Option<Duration>,
+    ) {
         // ALLOW: false positive: self borrowed both mutable and immutable
         #[allow(clippy::option_if_let_else)]
-        if let Some(summary) = self.fds.get_mut(&fd) {
-            let opsize: u64 = cap[2].parse().unwrap();
-            let bytes: u64 = cap[3].parse().unwrap();
-            summary.update_write(opsize, bytes);
+        if let Some(FdEntry { summary, .. }) =
+            self.fds.borrow_mut().get_mut(&fd)
+        {
+            summary.update_write(opsize, bytes, duration);
         } else {
-            self.verbose(format!("[write] unknown fd {fd}"));
+            self.verbose(format!("[{syscall}] unknown fd {fd}"));
         }
     }
 
-    fn finish<F>(&mut self, fd: u32, syscall: &str, f: F)
+    fn finish<F>(&self, fd: u32, syscall: &str, f: F)
     where
         F: Fn(Summary) + Copy,
     {
-        if let Some(summary) = self.fds.remove(&fd) {
+        let entry = self.fds.borrow_mut().remove(&fd);
+
+        if let Some(FdEntry { summary, .. }) = entry {
             self.debug(format!("[{}] {} => {}", syscall, fd, summary.file));
-            f(summary);
+            self.emit(summary, f);
         } else {
             self.verbose(format!("[{syscall}] unknown fd {fd}"));
         }
     }
 
-    fn join_paths(&self, dirfd: &str, pathname: &str) -> String {
-        if dirfd == "AT_FDCWD" {
-            String::from(pathname)
-        } else {
-            let dirfd: u32 = dirfd.parse().unwrap();
+    fn join_paths(&self, dirfd: Option<u32>, pathname: &str) -> String {
+        let fds = self.fds.borrow();
 
-            self.fds.get(&dirfd).map_or_else(
-                || String::from(pathname),
-                |dir_summary| {
-                    let mut path = PathBuf::new();
-                    path.push(dir_summary.file.clone());
-                    path.push(pathname);
+        dirfd.and_then(|dirfd| fds.get(&dirfd)).map_or_else(
+            || String::from(pathname),
+            |dir| {
+                let mut path = PathBuf::new();
+                path.push(dir.summary.file.clone());
+                path.push(pathname);
 
-                    path.to_str()
-                        .map_or_else(|| String::from(pathname), String::from)
-                },
-            )
-        }
+                path.to_str()
+                    .map_or_else(|| String::from(pathname), String::from)
+            },
+        )
     }
 
     fn debug<S: AsRef<str>>(&self, message: S) {
-        log::debug(message, self.config);
+        log::debug(self.timestamped(message), &self.config);
     }
 
     fn verbose<S: AsRef<str>>(&self, message: S) {
-        log::verbose(message, self.config);
+        log::verbose(self.timestamped(message), &self.config);
+    }
+
+    fn warn<S: AsRef<str>>(&self, message: S) {
+        log::warn(self.timestamped(message));
+    }
+
+    fn timestamped<S: AsRef<str>>(&self, message: S) -> String {
+        let message = message.as_ref();
+
+        self.timestamp.map_or_else(
+            || String::from(message),
+            |timestamp| {
+                format!(
+                    "[{}.{:06}] {message}",
+                    timestamp.as_secs(),
+                    timestamp.subsec_micros()
+                )
+            },
+        )
     }
 }
 
 // ----------------------------------------------------------------------------
-// regexes
+// traces
 // ----------------------------------------------------------------------------
 
-static RE_CLONE: LazyLock<Regex> =
-    LazyLock::new(|| Regex::new(r"^clone\(.*\)\s+= (\d+)$").unwrap());
+/// The analysis of a trace that is read line by line. With `strace -f`, all
+/// processes are traced into a single trace and each line starts with its
+/// PID, the first one being the analyzed process itself.
+pub(crate) struct Trace<'a> {
+    analysis: &'a mut Analysis,
 
-static RE_CLOSE: LazyLock<Regex> = LazyLock::new(|| {
-    Regex::new(r"^close\((\d+)\)\s+= (-?\d+)\s*([A-Z]*).*$").unwrap()
-});
+    /// The trace file, child traces of `strace -ff` are looked up next to
+    /// it. Streams have none.
+    input: Option<&'a Path>,
 
-static RE_CREAT: LazyLock<Regex> = LazyLock::new(|| {
-    Regex::new(r#"^creat\("([^"]+)", .+\)\s+= (\d+)$"#).unwrap()
-});
+    root: Option<u32>,
+    processes: HashMap<u32, Analysis>,
 
-static RE_DUP: LazyLock<Regex> =
-    LazyLock::new(|| Regex::new(r"^dup\((\d+)\)\s+= (\d+)$").unwrap());
+    /// Children that were forked before their parent's clone returned.
+    adopted: HashSet<u32>,
 
-static RE_DUP2: LazyLock<Regex> =
-    LazyLock::new(|| Regex::new(r"^dup2\((\d+), \d+\)\s+= (\d+)$").unwrap());
+    /// Whether the user has been warned about unfollowed child traces.
+    warned: bool,
+}
 
-static RE_FCNTL_DUP: LazyLock<Regex> = LazyLock::new(|| {
-    Regex::new(r"^fcntl\((\d+), F_DUPFD, \d+\)\s+= (\d+)$").unwrap()
-});
+impl<'a> Trace<'a> {
+    pub(crate) fn new(
+        analysis: &'a mut Analysis,
+        input: Option<&'a Path>,
+    ) -> Self {
+        Self {
+            root: analysis.pid,
+            analysis,
+            input,
+            processes: HashMap::new(),
+            adopted: HashSet::new(),
+            warned: false,
+        }
+    }
+
+    /// Analyzes a single line of the trace.
+    pub(crate) fn analyze<F>(&mut self, line: &str, f: F) -> Result<()>
+    where
+        F: Fn(Summary) + Copy,
+    {
+        let event = Event::parse(line);
+
+        let process = match event.pid {
+            Some(pid) if *self.root.get_or_insert(pid) != pid => {
+                if !self.processes.contains_key(&pid) {
+                    let process = self.spawn(pid);
+                    self.processes.insert(pid, process);
+                }
+
+                // UNWRAP: inserted above
+                self.processes.get_mut(&pid).unwrap()
+            }
+
+            Some(pid) => {
+                self.analysis.pid = Some(pid);
+                &mut *self.analysis
+            }
+
+            None => &mut *self.analysis,
+        };
+
+        let resumed;
+
+        let event = match process.resume(&event) {
+            Resume::Complete => event,
+            Resume::Unfinished => return Ok(()),
+            Resume::Resumed(timestamp, call) => {
+                resumed = call;
+
+                Event {
+                    timestamp,
+                    call: &resumed,
+                    ..event
+                }
+            }
+        };
+
+        let Some((child, shared_files)) = process.dispatch(&event, f) else {
+            return Ok(());
+        };
+
+        if self.adopted.remove(&child) {
+            self.analysis
+                .debug(format!("[clone] {child} already forked"));
+        } else if let Some(pid) = event.pid {
+            let forked = process.fork(child, shared_files);
+
+            self.analysis.debug(format!("[clone] {pid} => {child}"));
+
+            match self.processes.entry(child) {
+                Entry::Occupied(mut entry) => {
+                    entry.get_mut().inherit(forked, f);
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(forked);
+                }
+            }
+        } else if let Some(input) = self.input {
+            self.analysis.follow(child, shared_files, input, f)?;
+        } else if !self.warned {
+            self.warned = true;
+
+            self.analysis.warn(format!(
+                "can't follow child process {child} of a trace from a \
+                 stream, child traces of strace -ff are ignored, use \
+                 strace -f instead"
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Registers the thread `tid` of the traced process, which shares its
+    /// file descriptor table, e.g. the existing threads of a process that
+    /// strace attaches to.
+    pub(crate) fn thread(&mut self, tid: u32) {
+        if self.root == Some(tid) || self.processes.contains_key(&tid) {
+            return;
+        }
+
+        self.analysis.debug(format!("[attach] thread {tid}"));
+
+        let thread = self.analysis.fork(tid, true);
+        self.processes.insert(tid, thread);
+    }
+
+    /// Returns the analysis of the process `pid` that shows up in the trace
+    /// for the first time. If exactly one process waits for its clone to
+    /// return, e.g. a `vfork`, it is the child of that one. Otherwise its
+    /// file descriptors are taken over from the parent once the clone
+    /// returns, see [`Analysis::inherit`].
+    fn spawn(&mut self, pid: u32) -> Analysis {
+        let mut parents = std::iter::once(&*self.analysis)
+            .chain(self.processes.values())
+            .filter_map(|process| Some((process, process.pending_clone()?)));
+
+        if let (Some((parent, shared_files)), None) =
+            (parents.next(), parents.next())
+        {
+            let child = parent.fork(pid, shared_files);
+
+            self.adopted.insert(pid);
+            self.analysis.debug(format!("[clone] {pid} forked early"));
+
+            return child;
+        }
+
+        Analysis {
+            pid: Some(pid),
+            fds: Fds::default(),
+            tree: Rc::clone(&self.analysis.tree),
+            ..Analysis::new(self.analysis.config.clone())
+        }
+    }
+
+    /// Calls `f` for every file descriptor that is still open.
+    pub(crate) fn flush<F>(&self, f: F)
+    where
+        F: Fn(Summary) + Copy,
+    {
+        for process in self.processes.values() {
+            process.flush(f);
+        }
+
+        self.analysis.flush(f);
+    }
+
+    /// Returns the processes of the trace.
+    pub(crate) fn tree(&self) -> Ref<'_, Tree> {
+        self.analysis.tree.borrow()
+    }
+
+    /// Returns the summaries of all file descriptors that are still open,
+    /// without closing them.
+    pub(crate) fn snapshot(&self) -> Vec<Summary> {
+        let summaries = RefCell::new(vec![]);
 
-static RE_OPEN: LazyLock<Regex> = LazyLock::new(|| {
-    Regex::new(
-        // we're ignoring failures on purpose because they don't open fd
-        r#"^open\("([^"]+)", .+\)\s+= (\d+)$"#,
-    )
-    .unwrap()
-});
+        self.flush(|summary| summaries.borrow_mut().push(summary));
 
-static RE_OPENAT: LazyLock<Regex> = LazyLock::new(|| {
-    Regex::new(r#"^openat\((\d+|AT_FDCWD), "([^"]+)", .+\)\s+= (\d+)$"#)
-        .unwrap()
-});
+        summaries.into_inner()
+    }
+}
+
+// ----------------------------------------------------------------------------
+// events
+// ----------------------------------------------------------------------------
+
+/// The result of joining unfinished and resumed syscalls.
+enum Resume {
+    /// The syscall was complete to begin with.
+    Complete,
+
+    /// The syscall is unfinished and needs to wait for its resumption.
+    Unfinished,
+
+    /// The syscall has been resumed and joined with its unfinished part.
+    Resumed(Option<Duration>, String),
+}
+
+/// Returns the protocol of a socket based on its domain and type.
+fn socket_protocol(domain: &str, kind: &str) -> String {
+    match (domain, kind) {
+        ("AF_INET" | "AF_INET6", "SOCK_STREAM") => String::from("tcp"),
+        ("AF_INET" | "AF_INET6", "SOCK_DGRAM") => String::from("udp"),
+        ("AF_UNIX" | "AF_LOCAL", _) => String::from("unix"),
+        (domain, _) => domain.trim_start_matches("AF_").to_lowercase(),
+    }
+}
+
+/// Returns the name of a socket based on a socket address, e.g.
+/// `tcp:10.0.0.5:5432` or `unix:/run/foo.sock`.
+fn socket_name(socket: &Summary, address: &str) -> Option<String> {
+    let fields = parser::fields(address)?;
+
+    let field = |key: &str| {
+        fields
+            .iter()
+            .find_map(|(k, value)| (*k == key).then_some(*value))
+    };
+
+    let family = field("sa_family")?;
+
+    let address = if let Some(path) = field("sun_path") {
+        // abstract unix sockets are prefixed with @
+        let (prefix, path) = path
+            .strip_prefix('@')
+            .map_or(("", path), |path| ("@", path));
+
+        let path = parser::unquote(path).filter(|path| !path.is_empty())?;
 
-static RE_PIPE: LazyLock<Regex> = LazyLock::new(|| {
-    Regex::new(r"^pipe\(\[(\d+), (\d+)\]\)\s+= (\d+)$").unwrap()
-});
+        format!("{prefix}{path}")
+    } else if let Some(port) = field("sin_port") {
+        let port = parser::function_args(port, "htons")?;
+        let ip = parser::function_args(field("sin_addr")?, "inet_addr")?;
+        let ip = parser::unquote(ip.first()?)?;
 
-static RE_PREAD: LazyLock<Regex> = LazyLock::new(|| {
-    Regex::new(r"^pread\((\d+),.*, (\d+), \d+\)\s+= (\d+)$").unwrap()
-});
+        format!("{ip}:{}", port.first()?)
+    } else {
+        let port = parser::function_args(field("sin6_port")?, "htons")?;
 
-static RE_PWRITE: LazyLock<Regex> = LazyLock::new(|| {
-    Regex::new(r"^pwrite\((\d+),.*, (\d+), \d+\)\s+= (\d+)$").unwrap()
-});
+        let ip = fields.iter().find_map(|(_, value)| {
+            parser::function_args(value, "inet_pton")
+        })?;
 
-static RE_READ: LazyLock<Regex> = LazyLock::new(|| {
-    Regex::new(r"^read\((\d+),.*, (\d+)\)\s+= (\d+)$").unwrap()
-});
+        let ip = parser::unquote(ip.get(1)?)?;
 
-static RE_SOCKET: LazyLock<Regex> =
-    LazyLock::new(|| Regex::new(r"^socket\(.*\)\s+= (\d+)$").unwrap());
+        format!("[{ip}]:{}", port.first()?)
+    };
 
-static RE_WRITE: LazyLock<Regex> = LazyLock::new(|| {
-    Regex::new(r"^write\((\d+),.*, (\d+)\)\s+= (\d+)$").unwrap()
-});
+    let protocol = socket
+        .protocol
+        .clone()
+        .unwrap_or_else(|| socket_protocol(family, ""));
+
+    Some(format!("{protocol}:{address}"))
+}
 
 // ----------------------------------------------------------------------------
 // tests
@@ -516,7 +1568,10 @@ pub mod tests {
     use assert_fs::prelude::*;
     use predicates::prelude::*;
 
+    use std::collections::BTreeMap;
+
     use super::*;
+    use crate::summary::Timing;
 
     #[test]
     fn analyze_dd() {
@@ -557,18 +1612,1015 @@ pub mod tests {
             file: "/dev/zero".into(),
             read_freq: HashMap::from([(1_048_576, 1024)]),
             write_freq: HashMap::default(),
+            read_returned: HashMap::from([(1_048_576, 1024)]),
             read_bytes: 1_073_741_824,
             write_bytes: 0,
+            opens: 1,
+            ..Summary::new("/dev/zero")
         }));
 
         assert!(summaries.contains(&Summary {
             file: "/dev/null".into(),
             read_freq: HashMap::default(),
             write_freq: HashMap::from([(1_048_576, 1024)]),
+            write_returned: HashMap::from([(1_048_576, 1024)]),
             read_bytes: 0,
             write_bytes: 1_073_741_824,
+            opens: 1,
+            ..Summary::new("/dev/null")
         }));
 
         temp.close().unwrap();
     }
+
+    fn analyze_trace(trace: &str) -> Vec<Summary> {
+        let temp = assert_fs::TempDir::new().unwrap();
+
+        let file = temp.child("trace.strace");
+        file.write_str(trace).unwrap();
+
+        let config = Config::default();
+        let summaries = RefCell::new(vec![]);
+        let mut analysis = Analysis::new(config);
+
+        analysis
+            .analyze(file.path(), |summary| {
+                summaries.borrow_mut().push(summary);
+            })
+            .unwrap();
+
+        temp.close().unwrap();
+
+        summaries.into_inner()
+    }
+
+    #[test]
+    fn analyze_timestamps() {
+        let expected = Summary {
+            file: "data.bin".into(),
+            read_freq: HashMap::from([(4096, 2)]),
+            write_freq: HashMap::default(),
+            read_returned: HashMap::from([(4096, 1), (2048, 1)]),
+            read_short: 1,
+            read_bytes: 6144,
+            write_bytes: 0,
+            opens: 1,
+            ..Summary::new("data.bin")
+        };
+
+        for (first, second, third) in [
+            ("12:00:01", "12:00:01", "12:00:02"),
+            ("12:00:01.000001", "12:00:01.250000", "12:00:02.500000"),
+            ("1700000001.000001", "1700000001.250000", "1700000002.5"),
+        ] {
+            let trace = format!(
+                "{first} openat(AT_FDCWD, \"data.bin\", O_RDONLY) = 3\n\
+                 {second} read(3, \"\"..., 4096) = 4096\n\
+                 {third} read(3, \"\"..., 4096) = 2048\n"
+            );
+
+            let summaries = analyze_trace(&trace);
+
+            assert!(summaries.contains(&expected), "{first}: {summaries:?}");
+        }
+    }
+
+    #[test]
+    fn analyze_durations() {
+        let trace = "\
+            openat(AT_FDCWD, \"data.bin\", O_RDWR) = 3 <0.000010>\n\
+            read(3, \"\"..., 4096) = 4096 <0.000100>\n\
+            read(3, \"\"..., 4096) = 4096 <0.000300>\n\
+            write(3, \"\"..., 512) = 512 <0.002000>\n\
+            close(3) = 0 <0.000005>\n";
+
+        let summaries = analyze_trace(trace);
+
+        assert!(summaries.contains(&Summary {
+            read_freq: HashMap::from([(4096, 2)]),
+            write_freq: HashMap::from([(512, 1)]),
+            read_returned: HashMap::from([(4096, 2)]),
+            write_returned: HashMap::from([(512, 1)]),
+            read_bytes: 8192,
+            write_bytes: 512,
+            read_time: Some(Timing {
+                ops: 2,
+                total: Duration::from_micros(400),
+                max: Duration::from_micros(300),
+            }),
+            write_time: Some(Timing {
+                ops: 1,
+                total: Duration::from_millis(2),
+                max: Duration::from_millis(2),
+            }),
+            opens: 1,
+            ..Summary::new("data.bin")
+        }));
+    }
+
+    #[test]
+    fn analyze_multiple_processes() {
+        let trace = "\
+            100 openat(AT_FDCWD, \"in.txt\", O_RDONLY) = 3\n\
+            100 clone(child_stack=NULL, flags=SIGCHLD) = 101\n\
+            101 openat(AT_FDCWD, \"out.txt\", O_WRONLY|O_CREAT, 0666) = 4\n\
+            100 read(3, \"\"..., 64) = 64\n\
+            101 write(4, \"\"..., 100) = 100\n\
+            [pid   101] write(1, \"\"..., 10) = 10\n\
+            101 close(4) = 0\n\
+            100 close(3) = 0\n";
+
+        let summaries = analyze_trace(trace);
+
+        assert!(summaries.contains(&Summary {
+            read_freq: HashMap::from([(64, 1)]),
+            read_returned: HashMap::from([(64, 1)]),
+            read_bytes: 64,
+            pid: Some(100),
+            opens: 1,
+            ..Summary::new("in.txt")
+        }));
+
+        assert!(summaries.contains(&Summary {
+            write_freq: HashMap::from([(100, 1)]),
+            write_returned: HashMap::from([(100, 1)]),
+            write_bytes: 100,
+            pid: Some(101),
+            opens: 1,
+            ..Summary::new("out.txt")
+        }));
+
+        assert!(summaries.contains(&Summary {
+            write_freq: HashMap::from([(10, 1)]),
+            write_returned: HashMap::from([(10, 1)]),
+            write_bytes: 10,
+            pid: Some(101),
+            ..Summary::new("STDOUT")
+        }));
+    }
+
+    #[test]
+    fn analyze_threads_and_forks() {
+        let trace = "\
+            100 openat(AT_FDCWD, \"db\", O_RDWR) = 3\n\
+            100 read(3, \"\"..., 100) = 100\n\
+            100 clone3({flags=CLONE_VM|CLONE_FILES|CLONE_THREAD, exit_signal=0} => {parent_tid=[101]}, 88) = 101\n\
+            101 openat(AT_FDCWD, \"log\", O_WRONLY) = 4\n\
+            101 write(3, \"\"..., 10) = 10\n\
+            100 write(4, \"\"..., 20) = 20\n\
+            100 clone(child_stack=NULL, flags=SIGCHLD) = 102\n\
+            102 read(3, \"\"..., 50) = 50\n\
+            102 close(3) = 0\n\
+            101 close(4) = 0\n\
+            100 close(3) = 0\n";
+
+        let summaries = analyze_trace(trace);
+
+        // the thread shares the file descriptors of its process
+        assert!(summaries.contains(&Summary {
+            read_freq: HashMap::from([(100, 1)]),
+            read_returned: HashMap::from([(100, 1)]),
+            read_bytes: 100,
+            write_freq: HashMap::from([(10, 1)]),
+            write_returned: HashMap::from([(10, 1)]),
+            write_bytes: 10,
+            pid: Some(100),
+            opens: 1,
+            ..Summary::new("db")
+        }));
+
+        assert!(summaries.contains(&Summary {
+            write_freq: HashMap::from([(20, 1)]),
+            write_returned: HashMap::from([(20, 1)]),
+            write_bytes: 20,
+            pid: Some(101),
+            opens: 1,
+            ..Summary::new("log")
+        }));
+
+        // the forked process counts only its own I/O
+        assert!(summaries.contains(&Summary {
+            read_freq: HashMap::from([(50, 1)]),
+            read_returned: HashMap::from([(50, 1)]),
+            read_bytes: 50,
+            pid: Some(102),
+            ..Summary::new("db")
+        }));
+
+        // the shared standard streams are reported once
+        let stdout = summaries
+            .iter()
+            .filter(|summary| summary.file == "STDOUT")
+            .count();
+
+        assert_eq!(stdout, 2);
+    }
+
+    #[test]
+    fn analyze_processes() {
+        let temp = assert_fs::TempDir::new().unwrap();
+
+        let file = temp.child("trace.strace");
+        file.write_str(
+            "\
+            100 execve(\"/usr/bin/make\", [\"make\"], 0x7ffd /* 2 vars */) = 0\n\
+            100 vfork() = 101\n\
+            101 execve(\"/usr/bin/gcc\", [\"gcc\", \"-c\"], 0x7ffd /* 2 vars */) = 0\n\
+            100 clone(child_stack=NULL, flags=SIGCHLD) = 102\n",
+        )
+        .unwrap();
+
+        let mut analysis = Analysis::new(Config::default());
+        analysis.summaries(file.path()).unwrap();
+
+        temp.close().unwrap();
+
+        assert_eq!(
+            analysis.processes(),
+            vec![
+                Process {
+                    pid: Some(100),
+                    parent: None,
+                    program: Some(String::from("/usr/bin/make")),
+                    args: vec![String::from("make")],
+                },
+                Process {
+                    pid: Some(101),
+                    parent: Some(100),
+                    program: Some(String::from("/usr/bin/gcc")),
+                    args: vec![String::from("gcc"), String::from("-c")],
+                },
+                Process {
+                    pid: Some(102),
+                    parent: Some(100),
+                    program: Some(String::from("/usr/bin/make")),
+                    args: vec![String::from("make")],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn analyze_programs() {
+        let trace = "\
+            100 execve(\"/usr/local/bin/gcc\", [\"gcc\"], 0x7ffd /* 2 vars */) = -1 ENOENT (No such file or directory)\n\
+            100 execve(\"/usr/bin/gcc\", [\"gcc\"], 0x7ffd /* 2 vars */) = 0\n\
+            100 openat(AT_FDCWD, \"foo.c\", O_RDONLY) = 3\n\
+            100 read(3, \"\"..., 100) = 100\n\
+            100 close(3) = 0\n";
+
+        let summaries = analyze_trace(trace);
+
+        assert!(summaries.contains(&Summary {
+            read_freq: HashMap::from([(100, 1)]),
+            read_returned: HashMap::from([(100, 1)]),
+            read_bytes: 100,
+            pid: Some(100),
+            program: Some(String::from("/usr/bin/gcc")),
+            opens: 1,
+            ..Summary::new("foo.c")
+        }));
+
+        // the failed lookup is done by the program that calls execve
+        assert!(summaries.contains(&Summary {
+            pid: Some(100),
+            errors: BTreeMap::from([(
+                (String::from("execve"), String::from("ENOENT")),
+                1
+            )]),
+            ..Summary::new("/usr/local/bin/gcc")
+        }));
+    }
+
+    #[test]
+    fn analyze_exec_with_open_files() {
+        let trace = "\
+            100 execve(\"/bin/sh\", [\"sh\", \"build.sh\"], 0x7ffd /* 2 vars */) = 0\n\
+            100 openat(AT_FDCWD, \"build.log\", O_WRONLY|O_CREAT, 0644) = 3\n\
+            100 write(3, \"\"..., 10) = 10\n\
+            100 execve(\"/usr/bin/gcc\", [\"gcc\", \"foo.c\"], 0x7ffd /* 2 vars */) = 0\n\
+            100 write(3, \"\"..., 1000) = 1000\n\
+            100 write(3, \"\"..., 1000) = 1000\n\
+            100 close(3) = 0\n";
+
+        let summaries = analyze_trace(trace);
+
+        // the shell's write is reported for the shell ...
+        assert!(summaries.contains(&Summary {
+            write_freq: HashMap::from([(10, 1)]),
+            write_returned: HashMap::from([(10, 1)]),
+            write_bytes: 10,
+            pid: Some(100),
+            program: Some(String::from("/bin/sh")),
+            opens: 1,
+            ..Summary::new("build.log")
+        }));
+
+        // ... and the compiler's writes to the inherited fd for gcc
+        assert!(summaries.contains(&Summary {
+            write_freq: HashMap::from([(1000, 2)]),
+            write_returned: HashMap::from([(1000, 2)]),
+            write_bytes: 2000,
+            pid: Some(100),
+            program: Some(String::from("/usr/bin/gcc")),
+            ..Summary::new("build.log")
+        }));
+    }
+
+    #[test]
+    fn analyze_exec_with_cloexec() {
+        let trace = "\
+            100 pipe2([3, 4], O_CLOEXEC) = 0\n\
+            100 write(4, \"\"..., 10) = 10\n\
+            100 openat(AT_FDCWD, \"data\", O_RDONLY) = 5\n\
+            100 openat(AT_FDCWD, \"lock\", O_RDONLY) = 6\n\
+            100 fcntl(6, F_SETFD, FD_CLOEXEC) = 0\n\
+            100 execve(\"/usr/bin/cat\", [\"cat\"], 0x7ffd /* 2 vars */) = 0\n\
+            100 read(3, \"\"..., 100) = 100\n\
+            100 read(5, \"\"..., 100) = 100\n";
+
+        let summaries = analyze_trace(trace);
+
+        // both pipe ends are closed by the exec, cat can't read from them
+        let pipes: Vec<_> = summaries
+            .iter()
+            .filter(|summary| summary.file == "PIPE")
+            .collect();
+
+        assert_eq!(pipes.len(), 2);
+        assert!(pipes.iter().all(|pipe| pipe.program.is_none()));
+        assert!(pipes.iter().all(|pipe| pipe.read_bytes == 0));
+
+        assert!(summaries.contains(&Summary {
+            pid: Some(100),
+            opens: 1,
+            ..Summary::new("lock")
+        }));
+
+        assert!(!summaries.iter().any(
+            |summary| summary.file == "lock" && summary.program.is_some()
+        ));
+
+        // the file without the flag stays open for cat
+        assert!(summaries.contains(&Summary {
+            read_freq: HashMap::from([(100, 1)]),
+            read_returned: HashMap::from([(100, 1)]),
+            read_bytes: 100,
+            pid: Some(100),
+            program: Some(String::from("/usr/bin/cat")),
+            ..Summary::new("data")
+        }));
+    }
+
+    #[test]
+    fn analyze_close_range() {
+        let trace = "\
+            100 openat(AT_FDCWD, \"db\", O_RDWR) = 3\n\
+            100 socketpair(AF_UNIX, SOCK_STREAM, 0, [4, 5]) = 0\n\
+            100 write(4, \"\"..., 10) = 10\n\
+            100 clone3({flags=CLONE_VM|CLONE_FILES, exit_signal=SIGCHLD} => {parent_tid=[101]}, 88) = 101\n\
+            101 close_range(3, ~0U, CLOSE_RANGE_UNSHARE) = 0\n\
+            100 read(3, \"\"..., 100) = 100\n\
+            100 close_range(4, 5, 0) = 0\n\
+            100 read(4, \"\"..., 100) = 100\n";
+
+        let summaries = analyze_trace(trace);
+
+        // the sockets are closed by the parent's close_range
+        assert!(summaries.contains(&Summary {
+            write_freq: HashMap::from([(10, 1)]),
+            write_returned: HashMap::from([(10, 1)]),
+            write_bytes: 10,
+            pid: Some(100),
+            ..Summary::socket(Some(String::from("unix")))
+        }));
+
+        assert!(summaries.contains(&Summary {
+            pid: Some(100),
+            ..Summary::socket(Some(String::from("unix")))
+        }));
+
+        // the child closed only its own copy of the database
+        assert!(summaries.contains(&Summary {
+            pid: Some(101),
+            ..Summary::new("db")
+        }));
+
+        assert!(summaries.contains(&Summary {
+            read_freq: HashMap::from([(100, 1)]),
+            read_returned: HashMap::from([(100, 1)]),
+            read_bytes: 100,
+            pid: Some(100),
+            opens: 1,
+            ..Summary::new("db")
+        }));
+    }
+
+    #[test]
+    fn analyze_unshare_with_threads() {
+        let trace = "\
+            100 openat(AT_FDCWD, \"db\", O_RDWR) = 10\n\
+            100 clone3({flags=CLONE_VM|CLONE_FILES|CLONE_THREAD, exit_signal=0} => {parent_tid=[101]}, 88) = 101\n\
+            100 close_range(10, ~0U, CLOSE_RANGE_UNSHARE) = 0\n\
+            101 write(10, \"\"..., 10) = 10\n\
+            101 openat(AT_FDCWD, \"log\", O_WRONLY) = 11\n\
+            101 write(11, \"\"..., 20) = 20\n";
+
+        let summaries = analyze_trace(trace);
+
+        // the thread keeps using the table that the process left behind
+        assert!(summaries.contains(&Summary {
+            write_freq: HashMap::from([(10, 1)]),
+            write_returned: HashMap::from([(10, 1)]),
+            write_bytes: 10,
+            pid: Some(100),
+            opens: 1,
+            ..Summary::new("db")
+        }));
+
+        assert!(summaries.contains(&Summary {
+            write_freq: HashMap::from([(20, 1)]),
+            write_returned: HashMap::from([(20, 1)]),
+            write_bytes: 20,
+            pid: Some(100),
+            opens: 1,
+            ..Summary::new("log")
+        }));
+    }
+
+    #[test]
+    fn analyze_missing_child_trace() {
+        let trace = "\
+            openat(AT_FDCWD, \"in.txt\", O_RDONLY) = 3\n\
+            vfork() = 4242\n\
+            read(3, \"\"..., 4096) = 4096\n\
+            close(3) = 0\n";
+
+        let summaries = analyze_trace(trace);
+
+        assert!(summaries.contains(&Summary {
+            read_freq: HashMap::from([(4096, 1)]),
+            read_returned: HashMap::from([(4096, 1)]),
+            read_bytes: 4096,
+            opens: 1,
+            ..Summary::new("in.txt")
+        }));
+    }
+
+    #[test]
+    fn analyze_resumed() {
+        let trace = "\
+            100 openat(AT_FDCWD, \"in.txt\", O_RDONLY <unfinished ...>\n\
+            101 openat(AT_FDCWD, \"out.txt\", O_WRONLY) = 3\n\
+            100 <... openat resumed>) = 4\n\
+            100 read(4,  <unfinished ...>\n\
+            101 write(3, \"\"..., 100 <unfinished ...>\n\
+            100 <... read resumed>\"\"..., 4096) = 4096\n\
+            101 <... write resumed>) = 50\n\
+            100 close(4) = 0\n\
+            101 close(3) = 0\n";
+
+        let summaries = analyze_trace(trace);
+
+        assert!(summaries.contains(&Summary {
+            read_freq: HashMap::from([(4096, 1)]),
+            read_returned: HashMap::from([(4096, 1)]),
+            read_bytes: 4096,
+            pid: Some(100),
+            opens: 1,
+            ..Summary::new("in.txt")
+        }));
+
+        assert!(summaries.contains(&Summary {
+            write_freq: HashMap::from([(100, 1)]),
+            write_returned: HashMap::from([(50, 1)]),
+            write_short: 1,
+            write_bytes: 50,
+            pid: Some(101),
+            opens: 1,
+            ..Summary::new("out.txt")
+        }));
+    }
+
+    #[test]
+    fn analyze_resumed_vfork() {
+        let trace = "\
+            100 openat(AT_FDCWD, \"build.log\", O_WRONLY|O_CREAT, 0666) = 5\n\
+            100 vfork( <unfinished ...>\n\
+            101 dup2(5, 1) = 1\n\
+            101 execve(\"/usr/bin/gcc\", [\"gcc\"], 0x7ffd /* 2 vars */) = 0\n\
+            100 <... vfork resumed>) = 101\n\
+            101 write(1, \"\"..., 100) = 100\n\
+            100 openat(AT_FDCWD, \"out.log\", O_WRONLY) = 3\n\
+            100 dup2(3, 1) = 1\n\
+            100 clone(child_stack=NULL, flags=SIGCHLD <unfinished ...>\n\
+            102 write(1, \"\"..., 10) = 10\n\
+            100 <... clone resumed>) = 102\n";
+
+        let summaries = analyze_trace(trace);
+
+        assert!(summaries.contains(&Summary {
+            write_freq: HashMap::from([(100, 1)]),
+            write_returned: HashMap::from([(100, 1)]),
+            write_bytes: 100,
+            pid: Some(101),
+            program: Some(String::from("/usr/bin/gcc")),
+            ..Summary::new("build.log")
+        }));
+
+        assert!(summaries.contains(&Summary {
+            write_freq: HashMap::from([(10, 1)]),
+            write_returned: HashMap::from([(10, 1)]),
+            write_bytes: 10,
+            pid: Some(102),
+            ..Summary::new("out.log")
+        }));
+
+        assert!(!summaries.iter().any(|summary| summary.file == "DUP"));
+    }
+
+    #[test]
+    fn analyze_child_before_clone() {
+        let trace = "\
+            100 openat(AT_FDCWD, \"out.log\", O_WRONLY) = 3\n\
+            100 dup2(3, 1) = 1\n\
+            101 openat(AT_FDCWD, \"in.txt\", O_RDONLY) = 4\n\
+            100 clone(child_stack=NULL, flags=SIGCHLD) = 101\n\
+            101 read(4, \"\"..., 5) = 5\n\
+            101 write(1, \"\"..., 20) = 20\n";
+
+        let summaries = analyze_trace(trace);
+
+        // the parent's file descriptors win over placeholders of the child
+        assert!(summaries.contains(&Summary {
+            write_freq: HashMap::from([(20, 1)]),
+            write_returned: HashMap::from([(20, 1)]),
+            write_bytes: 20,
+            pid: Some(101),
+            ..Summary::new("out.log")
+        }));
+
+        assert!(summaries.contains(&Summary {
+            read_freq: HashMap::from([(5, 1)]),
+            read_returned: HashMap::from([(5, 1)]),
+            read_bytes: 5,
+            pid: Some(101),
+            opens: 1,
+            ..Summary::new("in.txt")
+        }));
+
+        assert!(!summaries.iter().any(
+            |summary| summary.pid == Some(101) && summary.file == "STDOUT"
+        ));
+    }
+
+    #[test]
+    fn analyze_stream() {
+        // the clone can't be followed, reading on must still work
+        let trace = "\
+            openat(AT_FDCWD, \"in.txt\", O_RDONLY) = 3\n\
+            clone(child_stack=NULL, flags=SIGCHLD) = 4242\n\
+            read(3, \"\"..., 4096) = 4096\n\
+            close(3) = 0\n";
+
+        let summaries = RefCell::new(vec![]);
+        let mut analysis = Analysis::new(Config::default());
+
+        analysis
+            .analyze_reader(trace.as_bytes(), |summary| {
+                summaries.borrow_mut().push(summary);
+            })
+            .unwrap();
+
+        assert!(summaries.into_inner().contains(&Summary {
+            read_freq: HashMap::from([(4096, 1)]),
+            read_returned: HashMap::from([(4096, 1)]),
+            read_bytes: 4096,
+            opens: 1,
+            ..Summary::new("in.txt")
+        }));
+    }
+
+    #[test]
+    fn analyze_threads_of_running_process() {
+        let summaries = RefCell::new(vec![]);
+        let collect = |summary| summaries.borrow_mut().push(summary);
+
+        let mut analysis = Analysis {
+            pid: Some(100),
+            ..Analysis::new(Config::default())
+        };
+
+        analysis
+            .fds
+            .borrow_mut()
+            .insert(7, FdEntry::new(Summary::new("db"), false));
+
+        let mut trace = Trace::new(&mut analysis, None);
+        trace.thread(101);
+
+        trace
+            .analyze("101 write(7, \"\"..., 10) = 10", collect)
+            .unwrap();
+        trace
+            .analyze("100 read(7, \"\"..., 5) = 5", collect)
+            .unwrap();
+        trace.analyze("101 close(7) = 0", collect).unwrap();
+
+        assert_eq!(
+            summaries.into_inner(),
+            vec![Summary {
+                read_freq: HashMap::from([(5, 1)]),
+                read_returned: HashMap::from([(5, 1)]),
+                read_bytes: 5,
+                write_freq: HashMap::from([(10, 1)]),
+                write_returned: HashMap::from([(10, 1)]),
+                write_bytes: 10,
+                pid: Some(101),
+                ..Summary::new("db")
+            }]
+        );
+    }
+
+    #[test]
+    fn analyze_snapshot() {
+        let summaries = RefCell::new(vec![]);
+        let collect = |summary| summaries.borrow_mut().push(summary);
+
+        let mut analysis = Analysis::new(Config::default());
+        let mut trace = Trace::new(&mut analysis, None);
+
+        trace
+            .analyze("openat(AT_FDCWD, \"in.txt\", O_RDONLY) = 3", collect)
+            .unwrap();
+        trace
+            .analyze("read(3, \"\"..., 4096) = 4096", collect)
+            .unwrap();
+
+        let expected = Summary {
+            read_freq: HashMap::from([(4096, 1)]),
+            read_returned: HashMap::from([(4096, 1)]),
+            read_bytes: 4096,
+            opens: 1,
+            ..Summary::new("in.txt")
+        };
+
+        assert!(trace.snapshot().contains(&expected));
+        assert!(summaries.borrow().is_empty());
+
+        // the snapshot doesn't close the file
+        trace
+            .analyze("read(3, \"\"..., 4096) = 4096", collect)
+            .unwrap();
+        trace.analyze("close(3) = 0", collect).unwrap();
+
+        assert_eq!(
+            summaries.into_inner(),
+            vec![Summary {
+                read_freq: HashMap::from([(4096, 2)]),
+                read_returned: HashMap::from([(4096, 2)]),
+                read_bytes: 8192,
+                ..expected
+            }]
+        );
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn analyze_compressed() {
+        let temp = assert_fs::TempDir::new().unwrap();
+
+        let parent = "\
+            clone(child_stack=NULL, flags=SIGCHLD) = 42\n\
+            openat(AT_FDCWD, \"in.txt\", O_RDONLY) = 3\n\
+            read(3, \"\"..., 4096) = 4096\n\
+            close(3) = 0\n";
+
+        let child = "\
+            openat(AT_FDCWD, \"out.txt\", O_WRONLY) = 3\n\
+            write(3, \"\"..., 512) = 512\n\
+            close(3) = 0\n";
+
+        let compress = |trace: &str| zstd::encode_all(trace.as_bytes(), 0);
+
+        let file = temp.child("trace.strace.41.zst");
+        file.write_binary(&compress(parent).unwrap()).unwrap();
+
+        temp.child("trace.strace.42.zst")
+            .write_binary(&compress(child).unwrap())
+            .unwrap();
+
+        let summaries = RefCell::new(vec![]);
+        let mut analysis = Analysis::new(Config::default());
+
+        analysis
+            .analyze(file.path(), |summary| {
+                summaries.borrow_mut().push(summary);
+            })
+            .unwrap();
+
+        temp.close().unwrap();
+
+        let summaries = summaries.into_inner();
+
+        assert!(summaries.contains(&Summary {
+            read_freq: HashMap::from([(4096, 1)]),
+            read_returned: HashMap::from([(4096, 1)]),
+            read_bytes: 4096,
+            opens: 1,
+            ..Summary::new("in.txt")
+        }));
+
+        assert!(summaries.contains(&Summary {
+            write_freq: HashMap::from([(512, 1)]),
+            write_returned: HashMap::from([(512, 1)]),
+            write_bytes: 512,
+            pid: Some(42),
+            opens: 1,
+            ..Summary::new("out.txt")
+        }));
+    }
+
+    #[test]
+    fn analyze_positional_and_vectored() {
+        let trace = "\
+            openat(AT_FDCWD, \"db\", O_RDWR) = 3\n\
+            pread64(3, \"\"..., 8192, 16384) = 8192\n\
+            pwrite64(3, \"\"..., 8192, 16384) = 8192\n\
+            readv(3, [{iov_base=\"\"..., iov_len=512}, \
+                {iov_base=\"\"..., iov_len=1024}], 2) = 1536\n\
+            preadv(3, [{iov_base=\"\"..., iov_len=4096}], 1, 0) = 4096\n\
+            preadv2(3, [{iov_base=\"\"..., iov_len=4096}], 1, 0, \
+                RWF_NOWAIT) = 100\n\
+            writev(3, [{iov_base=\"\"..., iov_len=10}, \
+                {iov_base=\"\"..., iov_len=20}], 2) = 30\n\
+            pwritev(3, [{iov_base=\"\"..., iov_len=8192}], 1, 0) = 8192\n\
+            pwritev2(3, [{iov_base=\"\"..., iov_len=8192}], 1, -1, \
+                RWF_APPEND) = 8192\n\
+            close(3) = 0\n";
+
+        let summaries = analyze_trace(trace);
+
+        assert!(summaries.contains(&Summary {
+            read_freq: HashMap::from([(8192, 1), (1536, 1), (4096, 2)]),
+            write_freq: HashMap::from([(8192, 3), (30, 1)]),
+            read_returned: HashMap::from([
+                (8192, 1),
+                (1536, 1),
+                (4096, 1),
+                (100, 1)
+            ]),
+            write_returned: HashMap::from([(8192, 3), (30, 1)]),
+            read_short: 1,
+            read_bytes: 8192 + 1536 + 4096 + 100,
+            write_bytes: 8192 + 30 + 8192 + 8192,
+            opens: 1,
+            ..Summary::new("db")
+        }));
+    }
+
+    #[test]
+    fn analyze_zero_copy() {
+        let trace = "\
+            openat(AT_FDCWD, \"src\", O_RDONLY) = 3\n\
+            openat(AT_FDCWD, \"dst\", O_WRONLY|O_CREAT, 0644) = 4\n\
+            copy_file_range(3, NULL, 4, NULL, 9223372035781033984, 0) = 4096\n\
+            copy_file_range(3, NULL, 4, NULL, 9223372035781033984, 0) = 0\n\
+            pipe([5, 6]) = 0\n\
+            splice(3, NULL, 6, NULL, 65536, SPLICE_F_MOVE) = 100\n\
+            tee(5, 1, 100, 0) = 100\n\
+            sendfile(4, 5, NULL, 100) = 100\n";
+
+        let summaries = analyze_trace(trace);
+
+        assert!(summaries.contains(&Summary {
+            read_freq: HashMap::from([
+                (9_223_372_035_781_033_984, 2),
+                (65536, 1)
+            ]),
+            read_bytes: 4196,
+            read_zero_copy: 4196,
+            opens: 1,
+            read_returned: HashMap::from([(4096, 1), (0, 1), (100, 1)]),
+            ..Summary::new("src")
+        }));
+
+        assert!(summaries.contains(&Summary {
+            write_freq: HashMap::from([
+                (9_223_372_035_781_033_984, 2),
+                (100, 1)
+            ]),
+            write_bytes: 4196,
+            write_zero_copy: 4196,
+            opens: 1,
+            write_returned: HashMap::from([(4096, 1), (0, 1), (100, 1)]),
+            ..Summary::new("dst")
+        }));
+
+        assert!(summaries.contains(&Summary {
+            write_freq: HashMap::from([(100, 1)]),
+            write_returned: HashMap::from([(100, 1)]),
+            write_bytes: 100,
+            write_zero_copy: 100,
+            ..Summary::new("STDOUT")
+        }));
+    }
+
+    #[test]
+    fn analyze_sockets() {
+        let trace = r#"socket(AF_INET, SOCK_STREAM|SOCK_CLOEXEC, IPPROTO_TCP) = 3
+connect(3, {sa_family=AF_INET, sin_port=htons(5432), sin_addr=inet_addr("10.0.0.5")}, 16) = -1 EINPROGRESS (Operation now in progress)
+send(3, ""..., 100, MSG_NOSIGNAL) = 100
+recvfrom(3, ""..., 8192, 0, NULL, NULL) = 42
+socket(AF_UNIX, SOCK_STREAM|SOCK_CLOEXEC, 0) = 4
+connect(4, {sa_family=AF_UNIX, sun_path="/run/foo.sock"}, 110) = 0
+sendmsg(4, {msg_name=NULL, msg_namelen=0, msg_iov=[{iov_base=""..., iov_len=10}, {iov_base=""..., iov_len=20}], msg_iovlen=2, msg_controllen=0, msg_flags=0}, MSG_NOSIGNAL) = 30
+socket(AF_INET6, SOCK_DGRAM, IPPROTO_IP) = 5
+sendto(5, ""..., 64, 0, {sa_family=AF_INET6, sin6_port=htons(53), sin6_flowinfo=htonl(0), inet_pton(AF_INET6, "::1", &sin6_addr), sin6_scope_id=0}, 28) = 64
+recvmsg(5, {msg_name={sa_family=AF_INET6, sin6_port=htons(53), sin6_flowinfo=htonl(0), inet_pton(AF_INET6, "::1", &sin6_addr), sin6_scope_id=0}, msg_namelen=28, msg_iov=[{iov_base=""..., iov_len=512}], msg_iovlen=1, msg_controllen=0, msg_flags=0}, 0) = 128
+socket(AF_INET, SOCK_STREAM, IPPROTO_TCP) = 6
+bind(6, {sa_family=AF_INET, sin_port=htons(8080), sin_addr=inet_addr("0.0.0.0")}, 16) = 0
+accept4(6, {sa_family=AF_INET, sin_port=htons(54321), sin_addr=inet_addr("10.0.0.9")}, [16], SOCK_CLOEXEC) = 7
+recv(7, ""..., 4096, 0) = 4096
+socket(AF_INET, SOCK_DGRAM, IPPROTO_IP) = 8
+connect(8, {sa_family=AF_INET, sin_port=htons(53), sin_addr=inet_addr("8.8.8.8")}, 16) = 0
+sendto(8, ""..., 40, MSG_NOSIGNAL, NULL, 0) = 40
+recvfrom(8, ""..., 512, 0, {sa_family=AF_INET, sin_port=htons(53), sin_addr=inet_addr("8.8.8.8")}, [28 => 16]) = 100
+"#;
+
+        let summaries = analyze_trace(trace);
+
+        let tcp = Some(String::from("tcp"));
+
+        assert!(summaries.contains(&Summary {
+            read_freq: HashMap::from([(8192, 1)]),
+            write_freq: HashMap::from([(100, 1)]),
+            read_returned: HashMap::from([(42, 1)]),
+            read_short: 1,
+            write_returned: HashMap::from([(100, 1)]),
+            read_bytes: 42,
+            write_bytes: 100,
+            protocol: tcp.clone(),
+            ..Summary::new("tcp:10.0.0.5:5432")
+        }));
+
+        assert!(summaries.contains(&Summary {
+            write_freq: HashMap::from([(30, 1)]),
+            write_returned: HashMap::from([(30, 1)]),
+            write_bytes: 30,
+            protocol: Some(String::from("unix")),
+            ..Summary::new("unix:/run/foo.sock")
+        }));
+
+        assert!(summaries.contains(&Summary {
+            read_freq: HashMap::from([(512, 1)]),
+            write_freq: HashMap::from([(64, 1)]),
+            read_returned: HashMap::from([(128, 1)]),
+            read_short: 1,
+            write_returned: HashMap::from([(64, 1)]),
+            read_bytes: 128,
+            write_bytes: 64,
+            protocol: Some(String::from("udp")),
+            ..Summary::new("udp:[::1]:53")
+        }));
+
+        assert!(summaries.contains(&Summary {
+            protocol: tcp.clone(),
+            ..Summary::new("tcp:0.0.0.0:8080")
+        }));
+
+        assert!(summaries.contains(&Summary {
+            read_freq: HashMap::from([(4096, 1)]),
+            read_returned: HashMap::from([(4096, 1)]),
+            read_bytes: 4096,
+            protocol: tcp,
+            ..Summary::new("tcp:10.0.0.9:54321")
+        }));
+
+        // the connected peer is named again by recvfrom
+        let dns: Vec<_> = summaries
+            .iter()
+            .filter(|summary| summary.file == "udp:8.8.8.8:53")
+            .collect();
+
+        assert_eq!(
+            dns,
+            [&Summary {
+                read_freq: HashMap::from([(512, 1)]),
+                write_freq: HashMap::from([(40, 1)]),
+                read_returned: HashMap::from([(100, 1)]),
+                read_short: 1,
+                write_returned: HashMap::from([(40, 1)]),
+                read_bytes: 100,
+                write_bytes: 40,
+                protocol: Some(String::from("udp")),
+                ..Summary::new("udp:8.8.8.8:53")
+            }]
+        );
+    }
+
+    #[test]
+    fn analyze_aggregate() {
+        let trace = "\
+            100 openat(AT_FDCWD, \"data.bin\", O_RDONLY) = 3\n\
+            100 read(3, \"\"..., 4096) = 4096\n\
+            100 close(3) = 0\n\
+            100 openat(AT_FDCWD, \"data.bin\", O_RDONLY) = 3\n\
+            100 read(3, \"\"..., 4096) = 1024\n\
+            100 close(3) = 0\n\
+            101 openat(AT_FDCWD, \"data.bin\", O_RDONLY) = 3\n\
+            101 read(3, \"\"..., 8192) = 8192\n\
+            101 close(3) = 0\n";
+
+        let summaries = aggregate(analyze_trace(trace));
+
+        let data: Vec<_> = summaries
+            .iter()
+            .filter(|summary| summary.file == "data.bin")
+            .collect();
+
+        assert_eq!(
+            data,
+            [&Summary {
+                read_freq: HashMap::from([(4096, 2), (8192, 1)]),
+                read_returned: HashMap::from([
+                    (4096, 1),
+                    (1024, 1),
+                    (8192, 1)
+                ]),
+                read_bytes: 4096 + 1024 + 8192,
+                read_short: 1,
+                opens: 3,
+                ..Summary::new("data.bin")
+            }]
+        );
+    }
+
+    #[test]
+    fn analyze_short_io() {
+        let trace = "\
+            openat(AT_FDCWD, \"in.txt\", O_RDONLY) = 3\n\
+            read(3, \"\"..., 4096) = 4096\n\
+            read(3, \"\"..., 4096) = 100\n\
+            read(3, \"\"..., 4096) = 0\n\
+            pipe([4, 5]) = 0\n\
+            write(5, \"\"..., 65536) = 4096\n\
+            write(5, \"\"..., 100) = 100\n\
+            sendfile(5, 3, NULL, 65536) = 0\n";
+
+        let summaries = analyze_trace(trace);
+
+        assert!(summaries.contains(&Summary {
+            read_freq: HashMap::from([(4096, 3), (65536, 1)]),
+            read_returned: HashMap::from([(4096, 1), (100, 1), (0, 2)]),
+            read_bytes: 4196,
+            read_short: 1,
+            read_eof: 1,
+            opens: 1,
+            ..Summary::new("in.txt")
+        }));
+
+        assert!(summaries.contains(&Summary {
+            write_freq: HashMap::from([(65536, 2), (100, 1)]),
+            write_returned: HashMap::from([(4096, 1), (100, 1), (0, 1)]),
+            write_bytes: 4196,
+            write_short: 1,
+            ..Summary::pipe()
+        }));
+    }
+
+    #[test]
+    fn analyze_errors() {
+        let trace = "\
+            openat(AT_FDCWD, \"app.conf\", O_RDONLY) = -1 ENOENT (No such file or directory)\n\
+            open(\"app.conf\", O_RDONLY) = -1 ENOENT (No such file or directory)\n\
+            openat(AT_FDCWD, \"app.conf\", O_RDONLY) = -1 EACCES (Permission denied)\n\
+            socket(AF_INET, SOCK_STREAM|SOCK_NONBLOCK, IPPROTO_TCP) = 3\n\
+            read(3, 0x7ffd0000, 4096) = -1 EAGAIN (Resource temporarily unavailable)\n\
+            read(3, 0x7ffd0000, 4096) = -1 EAGAIN (Resource temporarily unavailable)\n\
+            write(3, \"\"..., 10) = -1 EPIPE (Broken pipe)\n";
+
+        let summaries = analyze_trace(trace);
+
+        let error = |syscall: &str, error: &str| {
+            (String::from(syscall), String::from(error))
+        };
+
+        assert!(summaries.contains(&Summary {
+            errors: BTreeMap::from([
+                (error("open", "ENOENT"), 1),
+                (error("openat", "EACCES"), 1),
+                (error("openat", "ENOENT"), 1),
+            ]),
+            ..Summary::new("app.conf")
+        }));
+
+        assert!(summaries.contains(&Summary {
+            errors: BTreeMap::from([
+                (error("read", "EAGAIN"), 2),
+                (error("write", "EPIPE"), 1),
+            ]),
+            ..Summary::socket(Some(String::from("tcp")))
+        }));
+    }
 }
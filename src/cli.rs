@@ -23,14 +23,17 @@
  *                                                                           *
  * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * */
 
+use std::ffi::OsString;
 use std::io::IsTerminal;
+use std::os::unix::fs::FileTypeExt;
 use std::path::Path;
 
 use clap::builder::EnumValueParser;
+use clap::value_parser;
 use clap::{Arg, Command};
 use clap::{ArgAction, crate_description, crate_name, crate_version};
 
-use crate::output::Output;
+use strace_analyzer::Output;
 
 pub fn build() -> Command {
     let input = Arg::new("input")
@@ -38,13 +41,21 @@ pub fn build() -> Command {
         .long_help(
 "The primary output file name of the strace run. strace-analyzer will follow \
  other strace files created via the strace -ff flag. The followed files are \
- determined based on the clone syscalls that are encountered in the traces."
+ determined based on the clone, clone3, fork and vfork syscalls that are \
+ encountered in the traces. Missing files are skipped with a warning. \
+ Traces of multiple processes in a single file, created via the strace -f \
+ flag, are analyzed per process. Traces compressed with gzip, xz or zstd \
+ are decompressed. Use - to read the trace from stdin. \
+ Traces from stdin or a pipe, e.g. <(zcat trace.gz), are analyzed as they \
+ arrive. Child traces of strace -ff can't be followed in that case, trace \
+ with strace -f instead."
         )
         .action(ArgAction::Set)
         .required(true)
-        .value_parser(is_file);
+        .value_parser(is_input);
 
     let output_format = Arg::new("output_format")
+        .global(true)
         .long("output")
         .help("output format")
         .long_help("Specify output format of the report.")
@@ -60,13 +71,124 @@ pub fn build() -> Command {
             output_format.default_value("continuous")
         };
 
+    let aggregate = Arg::new("aggregate")
+        .global(true)
+        .long("aggregate")
+        .action(ArgAction::SetTrue)
+        .overrides_with("no_aggregate")
+        .help("merge summaries of the same file")
+        .long_help(
+"Merge the summaries of the same file, e.g. of a file that is opened and \
+ closed repeatedly or by multiple processes, and report how many times it \
+ was opened. This is the default for the table output."
+        )
+        .display_order(2);
+
+    let no_aggregate = Arg::new("no_aggregate")
+        .global(true)
+        .long("no-aggregate")
+        .action(ArgAction::SetTrue)
+        .overrides_with("aggregate")
+        .help("report each opened file separately")
+        .long_help(
+"Report each opened file separately, i.e. one summary per open file \
+ description."
+        )
+        .hide_short_help(true)
+        .display_order(2);
+
+    let histogram = Arg::new("histogram")
+        .global(true)
+        .long("histogram")
+        .action(ArgAction::SetTrue)
+        .help("show histograms of operation sizes")
+        .long_help(
+"Show a histogram of the requested buffer sizes of each file, together with \
+ the mode, mean, median and 99th percentile of the requested and of the \
+ actually returned sizes. The statistics of the returned sizes are always \
+ shown. Applies to the continuous and table output, the JSON output always \
+ contains the histograms."
+        )
+        .display_order(3);
+
+    let include = Arg::new("include")
+        .global(true)
+        .long("include")
+        .value_name("PATTERN")
+        .action(ArgAction::Append)
+        .help("show files matching a glob or re:regex")
+        .long_help(
+"Show files matching PATTERN, even if they are excluded. PATTERN is a glob, \
+ e.g. '/tmp/**', or a regex if prefixed with re:, e.g. 're:\\.csv$'. Can be \
+ given multiple times."
+        )
+        .display_order(4);
+
+    let exclude = Arg::new("exclude")
+        .global(true)
+        .long("exclude")
+        .value_name("PATTERN")
+        .action(ArgAction::Append)
+        .help("hide files matching a glob or re:regex")
+        .long_help(
+"Hide files matching PATTERN, see --include. Can be given multiple times. \
+ By default, system files like /usr/** or /proc/**, files in /tmp and the \
+ standard streams are hidden, unless the config file defines its own \
+ filters."
+        )
+        .display_order(4);
+
+    let config = Arg::new("config")
+        .global(true)
+        .long("config")
+        .value_name("FILE")
+        .action(ArgAction::Set)
+        .value_parser(is_file)
+        .help("config file")
+        .long_help(
+"Config file with include and exclude directives, one per line, e.g. \
+ 'include /tmp/**'. Its filters replace the built-in defaults. Defaults to \
+ $XDG_CONFIG_HOME/strace-analyzer/config, if it exists."
+        )
+        .hide_short_help(true)
+        .display_order(4);
+
+    let pid_filter = Arg::new("pid_filter")
+        .global(true)
+        .long("pid")
+        .value_name("PID")
+        .action(ArgAction::Set)
+        .value_parser(value_parser!(u32))
+        .help("report only process PID and its descendants")
+        .long_help(
+"Report only the I/O of process PID and of the processes it has created, \
+ directly or indirectly. Use the tree output to find the PID."
+        )
+        .display_order(5);
+
+    let program = Arg::new("program")
+        .global(true)
+        .long("program")
+        .value_name("NAME")
+        .action(ArgAction::Set)
+        .help("report only processes executing program NAME")
+        .long_help(
+"Report only the I/O of processes while they execute program NAME, e.g. \
+ gcc. NAME is matched against the file name of the executed program, or \
+ against its full path if NAME contains a slash. Use the programs output to \
+ see the I/O grouped by program."
+        )
+        .display_order(6);
+
     let debug = Arg::new("debug")
+        .global(true)
         .long("debug")
         .action(ArgAction::SetTrue)
         .long_help("Show debug output.")
         .hide_short_help(true);
 
     let verbose = Arg::new("verbose")
+        .global(true)
         .short('v')
         .long("verbose")
         .action(ArgAction::SetTrue)
@@ -85,13 +207,96 @@ pub fn build() -> Command {
         .hide_short_help(true)
         .action(ArgAction::Version);
 
+    let keep = Arg::new("keep")
+        .long("keep")
+        .action(ArgAction::SetTrue)
+        .help("keep the traces")
+        .long_help(
+"Keep the temporary directory with the traces instead of removing it after \
+ the analysis."
+        );
+
+    let command = Arg::new("command")
+        .value_name("COMMAND")
+        .help("command to trace")
+        .long_help(
+"The command to trace, followed by its arguments. Use -- to separate its \
+ options from the ones of strace-analyzer."
+        )
+        .action(ArgAction::Append)
+        .value_parser(value_parser!(OsString))
+        .num_args(1..)
+        .trailing_var_arg(true)
+        .allow_hyphen_values(true)
+        .required(true);
+
+    let run = Command::new("run")
+        .about("trace a command and analyze its traces")
+        .long_about(
+"Trace a command with strace -s 0 -ff into a temporary directory and \
+ analyze the traces once the command exits. Exits with the exit status of \
+ the command."
+        )
+        .arg(keep)
+        .arg(command)
+        .disable_help_flag(true)
+        .arg(help.clone());
+
+    let pid = Arg::new("pid")
+        .value_name("PID")
+        .help("process to attach to")
+        .long_help(
+"The process to attach to. Its threads and the child processes it creates \
+ while attached are traced as well."
+        )
+        .action(ArgAction::Set)
+        .value_parser(value_parser!(u32))
+        .required(true);
+
+    let interval = Arg::new("interval")
+        .long("interval")
+        .value_name("SECONDS")
+        .help("report interval")
+        .long_help("Report the summaries every SECONDS while attached.")
+        .action(ArgAction::Set)
+        .value_parser(value_parser!(u64).range(1..))
+        .default_value("10");
+
+    let attach = Command::new("attach")
+        .about("trace a running process and report periodically")
+        .long_about(
+"Attach strace -s 0 -f to a running process and report the summaries \
+ periodically, and once more when the process exits or on SIGINT, which \
+ detaches strace from the process again. File descriptors that are already \
+ open are named after /proc/PID/fd."
+        )
+        .arg(pid)
+        .arg(interval)
+        .disable_help_flag(true)
+        .arg(help.clone());
+
     Command::new(crate_name!())
         .version(crate_version!())
         .about(crate_description!())
-        .after_help("create traces with: strace -s 0 -ff -o cmd.strace cmd")
+        .after_help(
+            "create traces with: strace -s 0 -ff -o cmd.strace cmd\n\
+             or trace and analyze with: strace-analyzer run cmd\n\
+             or attach to a running process: strace-analyzer attach pid",
+        )
         .max_term_width(80)
+        .subcommand_negates_reqs(true)
+        .subcommand(run)
+        .subcommand(attach)
         .arg(input)
         .arg(output_format)
+        .arg(aggregate)
+        .arg(no_aggregate)
+        .arg(histogram)
+        .arg(include)
+        .arg(exclude)
+        .arg(config)
+        .arg(pid_filter)
+        .arg(program)
         .arg(debug)
         .arg(verbose)
         .disable_help_flag(true)
@@ -112,6 +317,21 @@ fn is_file(s: &str) -> Result<String, String> {
     }
 }
 
+fn is_input(s: &str) -> Result<String, String> {
+    let path = Path::new(&s);
+
+    let is_fifo = || {
+        path.metadata()
+            .is_ok_and(|metadata| metadata.file_type().is_fifo())
+    };
+
+    if s == "-" || is_fifo() {
+        Ok(String::from(s))
+    } else {
+        is_file(s)
+    }
+}
+
 // ----------------------------------------------------------------------------
 // tests
 // ----------------------------------------------------------------------------
@@ -0,0 +1,218 @@
+/* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * *
+ *                                                                           *
+ *  Copyright  (C)  2015-2024  Christian Krause                              *
+ *                                                                           *
+ *  Christian Krause  <christian.krause@mailbox.org>                         *
+ *                                                                           *
+ * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * *
+ *                                                                           *
+ *  This file is part of strace-analyzer.                                    *
+ *                                                                           *
+ *  strace-analyzer is free software: you can redistribute it and/or modify  *
+ *  it under the terms of the GNU General Public License as published by     *
+ *  the Free Software Foundation, either version 3 of the license, or any    *
+ *  later version.                                                           *
+ *                                                                           *
+ *  strace-analyzer is distributed in the hope that it will be useful, but   *
+ *  WITHOUT ANY WARRANTY; without even the implied warranty of               *
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU         *
+ *  General Public License for more details.                                 *
+ *                                                                           *
+ *  You should have received a copy of the GNU General Public License along  *
+ *  with strace-analyzer. If not, see <http://www.gnu.org/licenses/>.        *
+ *                                                                           *
+ * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * */
+
+//! Transparent decompression of traces.
+//!
+//! Compressed traces are detected by their magic bytes and decompressed as a
+//! stream. Child traces of `strace -ff` are expected to carry the same
+//! compression suffix as the primary trace, e.g. `cmd.strace.1234.zst`.
+
+use std::ffi::OsStr;
+use std::fmt;
+use std::io::BufRead;
+#[cfg(feature = "compression")]
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+#[cfg(not(feature = "compression"))]
+use anyhow::bail;
+
+/// File name suffixes of compressed traces.
+const SUFFIXES: &[&str] = &["gz", "xz", "zst"];
+
+/// Compression formats of traces.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Xz,
+    Zstd,
+}
+
+impl Compression {
+    /// Detects the compression format by the magic bytes at the start of a
+    /// trace.
+    #[must_use]
+    pub fn detect(magic: &[u8]) -> Self {
+        if magic.starts_with(&[0x1f, 0x8b]) {
+            Self::Gzip
+        } else if magic.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+            Self::Xz
+        } else if magic.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Self::Zstd
+        } else {
+            Self::None
+        }
+    }
+}
+
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::None => "uncompressed",
+            Self::Gzip => "gzip",
+            Self::Xz => "xz",
+            Self::Zstd => "zstd",
+        };
+
+        write!(f, "{name}")
+    }
+}
+
+/// Wraps `reader` in a decoder if the trace it reads is compressed.
+///
+/// # Errors
+///
+/// Returns an error if the start of the trace can't be read, if the decoder
+/// can't be created or, without the `compression` feature, if the trace is
+/// compressed.
+pub fn decode<'a, R>(mut reader: R) -> Result<Box<dyn BufRead + 'a>>
+where
+    R: BufRead + 'a,
+{
+    let compression = Compression::detect(reader.fill_buf()?);
+
+    let reader: Box<dyn BufRead + 'a> = match compression {
+        Compression::None => Box::new(reader),
+
+        #[cfg(feature = "compression")]
+        Compression::Gzip => Box::new(BufReader::new(
+            flate2::bufread::MultiGzDecoder::new(reader),
+        )),
+
+        #[cfg(feature = "compression")]
+        Compression::Xz => Box::new(BufReader::new(
+            liblzma::bufread::XzDecoder::new_multi_decoder(reader),
+        )),
+
+        #[cfg(feature = "compression")]
+        Compression::Zstd => {
+            Box::new(BufReader::new(zstd::Decoder::with_buffer(reader)?))
+        }
+
+        #[cfg(not(feature = "compression"))]
+        compression => {
+            bail!(
+                "{compression} compressed traces need the compression feature"
+            )
+        }
+    };
+
+    Ok(reader)
+}
+
+/// Splits a compression suffix off a trace name, e.g. `cmd.strace.1234.zst`
+/// becomes `cmd.strace.1234` and `zst`.
+#[must_use]
+pub fn split_suffix(path: &Path) -> (PathBuf, Option<&OsStr>) {
+    match path.extension() {
+        Some(suffix) if SUFFIXES.iter().any(|s| OsStr::new(s) == suffix) => {
+            (path.with_extension(""), Some(suffix))
+        }
+
+        _ => (path.to_path_buf(), None),
+    }
+}
+
+/// Returns the trace name of the child process `pid` of the trace `path`,
+/// which carries the same compression suffix, if such a trace exists.
+#[must_use]
+pub fn child_trace(path: &Path, pid: u32) -> PathBuf {
+    let (name, suffix) = split_suffix(path);
+    let trace = name.with_extension(pid.to_string());
+
+    suffix
+        .map(|suffix| {
+            let mut compressed = trace.clone().into_os_string();
+            compressed.push(".");
+            compressed.push(suffix);
+            PathBuf::from(compressed)
+        })
+        .filter(|compressed| compressed.exists())
+        .unwrap_or(trace)
+}
+
+// ----------------------------------------------------------------------------
+// tests
+// ----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect_compression() {
+        assert_eq!(
+            Compression::detect(b"\x1f\x8b\x08\x00"),
+            Compression::Gzip
+        );
+        assert_eq!(Compression::detect(b"\xfd7zXZ\x00\x00"), Compression::Xz);
+        assert_eq!(
+            Compression::detect(b"\x28\xb5\x2f\xfd\x04"),
+            Compression::Zstd
+        );
+        assert_eq!(Compression::detect(b"read(3, "), Compression::None);
+        assert_eq!(Compression::detect(b""), Compression::None);
+    }
+
+    #[test]
+    fn split_compression_suffix() {
+        let (name, suffix) = split_suffix(Path::new("cmd.strace.1234.zst"));
+        assert_eq!(name, Path::new("cmd.strace.1234"));
+        assert_eq!(suffix, Some(OsStr::new("zst")));
+
+        let (name, suffix) = split_suffix(Path::new("cmd.strace.1234"));
+        assert_eq!(name, Path::new("cmd.strace.1234"));
+        assert_eq!(suffix, None);
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn decode_compressed() {
+        use std::io::Write;
+
+        let trace = "close(3) = 0\n";
+
+        let mut gzip = flate2::write::GzEncoder::new(
+            vec![],
+            flate2::Compression::default(),
+        );
+        gzip.write_all(trace.as_bytes()).unwrap();
+        let gzip = gzip.finish().unwrap();
+
+        let mut xz = liblzma::write::XzEncoder::new(vec![], 6);
+        xz.write_all(trace.as_bytes()).unwrap();
+        let xz = xz.finish().unwrap();
+
+        let zstd = zstd::encode_all(trace.as_bytes(), 0).unwrap();
+
+        for compressed in [trace.as_bytes(), &gzip, &xz, &zstd] {
+            let mut lines = decode(compressed).unwrap().lines();
+            assert_eq!(lines.next().unwrap().unwrap(), "close(3) = 0");
+            assert!(lines.next().is_none());
+        }
+    }
+}
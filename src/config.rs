@@ -26,13 +26,32 @@
 use anyhow::{Context, Result};
 use clap::ArgMatches;
 
+use crate::filter::{Filter, default_config_file};
 use crate::output::Output;
 
-#[derive(Copy, Clone, Debug, Default)]
+// ALLOW: the flags map one-to-one to command-line flags
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
 pub struct Config {
     pub debug: bool,
     pub verbose: bool,
     pub output: Output,
+
+    /// Merge the summaries of the same file.
+    pub aggregate: bool,
+
+    /// Show histograms of the operation sizes of each file.
+    pub histogram: bool,
+
+    /// Decides which files are shown in the report.
+    pub filter: Filter,
+
+    /// Report only the I/O of this process and its descendants.
+    pub pid: Option<u32>,
+
+    /// Report only the I/O of processes executing this program.
+    pub program: Option<String>,
 }
 
 impl TryFrom<&ArgMatches> for Config {
@@ -41,16 +60,50 @@ impl TryFrom<&ArgMatches> for Config {
     fn try_from(args: &ArgMatches) -> Result<Self> {
         let debug = args.get_flag("debug");
         let verbose = args.get_flag("verbose");
+        let histogram = args.get_flag("histogram");
+        let pid = args.get_one::<u32>("pid_filter").copied();
+        let program = args.get_one::<String>("program").cloned();
 
         let output = args
             .get_one::<Output>("output_format")
             .copied()
             .with_context(|| "no output format specified")?;
 
+        // aggregation is the default for the table, other formats report
+        // every open file description on its own unless asked to aggregate
+        let aggregate = if args.get_flag("no_aggregate") {
+            false
+        } else {
+            args.get_flag("aggregate") || output.aggregates()
+        };
+
+        // a config file replaces the built-in filters
+        let mut filter = match args.get_one::<String>("config") {
+            Some(path) => Filter::load(path)?,
+            None => default_config_file()
+                .filter(|path| path.is_file())
+                .map_or_else(|| Ok(Filter::default()), Filter::load)?,
+        };
+
+        for pattern in args.get_many::<String>("include").into_iter().flatten()
+        {
+            filter.include(pattern)?;
+        }
+
+        for pattern in args.get_many::<String>("exclude").into_iter().flatten()
+        {
+            filter.exclude(pattern)?;
+        }
+
         Ok(Self {
             debug,
             verbose,
             output,
+            aggregate,
+            histogram,
+            filter,
+            pid,
+            program,
         })
     }
 }
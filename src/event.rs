@@ -0,0 +1,180 @@
+/* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * *
+ *                                                                           *
+ *  Copyright  (C)  2015-2024  Christian Krause                              *
+ *                                                                           *
+ *  Christian Krause  <christian.krause@mailbox.org>                         *
+ *                                                                           *
+ * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * *
+ *                                                                           *
+ *  This file is part of strace-analyzer.                                    *
+ *                                                                           *
+ *  strace-analyzer is free software: you can redistribute it and/or modify  *
+ *  it under the terms of the GNU General Public License as published by     *
+ *  the Free Software Foundation, either version 3 of the license, or any    *
+ *  later version.                                                           *
+ *                                                                           *
+ *  strace-analyzer is distributed in the hope that it will be useful, but   *
+ *  WITHOUT ANY WARRANTY; without even the implied warranty of               *
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU         *
+ *  General Public License for more details.                                 *
+ *                                                                           *
+ *  You should have received a copy of the GNU General Public License along  *
+ *  with strace-analyzer. If not, see <http://www.gnu.org/licenses/>.        *
+ *                                                                           *
+ * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * */
+
+use std::sync::LazyLock;
+use std::time::Duration;
+
+use regex::{Captures, Regex};
+
+/// A single line of strace output with its prefix and suffix stripped.
+pub struct Event<'a> {
+    /// PID of the process, if the trace was recorded with `-f` into a single
+    /// file.
+    pub pid: Option<u32>,
+
+    /// Time of the syscall, if the trace was recorded with `-t`, `-tt` or
+    /// `-ttt`.
+    pub timestamp: Option<Duration>,
+
+    /// Time spent in the syscall, if the trace was recorded with `-T`.
+    pub duration: Option<Duration>,
+
+    /// The syscall itself, starting with its name.
+    pub call: &'a str,
+}
+
+impl<'a> Event<'a> {
+    /// Parses a line of strace output.
+    ///
+    /// The line is expected to be a single, complete line of output without
+    /// the trailing newline.
+    #[must_use]
+    pub fn parse(line: &'a str) -> Self {
+        let mut call = line;
+
+        let pid = RE_PID.captures(call).and_then(|cap| {
+            let pid = cap.get(1).or_else(|| cap.get(2))?.as_str().parse().ok();
+
+            call = &call[cap[0].len()..];
+
+            pid
+        });
+
+        // out of range numbers are dropped, the syscall itself is still fine
+        let timestamp = RE_TIMESTAMP.captures(call).and_then(|cap| {
+            call = &call[cap[0].len()..];
+
+            parse_timestamp(&cap)
+        });
+
+        let duration = RE_DURATION.captures(call).and_then(|cap| {
+            call = &call[..call.len() - cap[0].len()];
+
+            parse_seconds(&cap[1], &cap[2])
+        });
+
+        Self {
+            pid,
+            timestamp,
+            duration,
+            call,
+        }
+    }
+}
+
+fn parse_timestamp(cap: &Captures) -> Option<Duration> {
+    if let (Some(seconds), Some(fraction)) = (cap.get(5), cap.get(6)) {
+        return parse_seconds(seconds.as_str(), fraction.as_str());
+    }
+
+    // UNWRAP: regexes restrict hours, minutes and seconds to two digits
+    let number = |i| cap.get(i).map_or(0, |m| m.as_str().parse().unwrap());
+    let seconds: u64 = number(1) * 3600 + number(2) * 60 + number(3);
+
+    cap.get(4).map_or_else(
+        || Some(Duration::from_secs(seconds)),
+        |fraction| parse_seconds(&seconds.to_string(), fraction.as_str()),
+    )
+}
+
+/// Parses seconds with their fraction, `None` if they are out of range.
+fn parse_seconds(seconds: &str, fraction: &str) -> Option<Duration> {
+    let seconds = seconds.parse().ok()?;
+    let nanos: u32 = fraction.parse().ok()?;
+
+    // UNWRAP: regexes restrict fractions to at most 9 digits
+    let digits = u32::try_from(fraction.len()).unwrap();
+
+    Some(Duration::new(seconds, nanos * 10_u32.pow(9 - digits)))
+}
+
+// ----------------------------------------------------------------------------
+// regexes
+// ----------------------------------------------------------------------------
+
+static RE_PID: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        // strace -o prints the bare PID, without -o it is framed
+        r"^(?:(\d+)|\[pid\s+(\d+)\])\s+",
+    )
+    .unwrap()
+});
+
+static RE_TIMESTAMP: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        // -t and -tt print the time of day, -ttt prints the unix epoch
+        r"^(?:(\d{2}):(\d{2}):(\d{2})(?:\.(\d{1,9}))?|(\d+)\.(\d{1,9}))\s+",
+    )
+    .unwrap()
+});
+
+static RE_DURATION: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\s+<(\d+)\.(\d{1,9})>$").unwrap());
+
+// ----------------------------------------------------------------------------
+// tests
+// ----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_timestamps() {
+        let event = Event::parse("12:34:56 close(3) = 0");
+        assert_eq!(event.timestamp, Some(Duration::from_secs(45_296)));
+        assert_eq!(event.call, "close(3) = 0");
+
+        let event = Event::parse("12:34:56.000250 close(3) = 0");
+        assert_eq!(event.timestamp, Some(Duration::new(45_296, 250_000)));
+
+        let event = Event::parse("1700000000.5 close(3) = 0");
+        assert_eq!(
+            event.timestamp,
+            Some(Duration::new(1_700_000_000, 500_000_000))
+        );
+
+        let event = Event::parse("close(3) = 0");
+        assert_eq!(event.timestamp, None);
+        assert_eq!(event.call, "close(3) = 0");
+
+        // out of range
+        let event = Event::parse("123456789012345678901234.5 close(3) = 0");
+        assert_eq!(event.timestamp, None);
+        assert_eq!(event.call, "close(3) = 0");
+    }
+
+    #[test]
+    fn parse_durations() {
+        let event = Event::parse("close(3) = 0 <0.000123>");
+        assert_eq!(event.duration, Some(Duration::from_micros(123)));
+        assert_eq!(event.call, "close(3) = 0");
+
+        // out of range
+        let event = Event::parse("close(3) = 0 <123456789012345678901234.5>");
+        assert_eq!(event.duration, None);
+        assert_eq!(event.call, "close(3) = 0");
+    }
+}
@@ -0,0 +1,262 @@
+/* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * *
+ *                                                                           *
+ *  Copyright  (C)  2015-2024  Christian Krause                              *
+ *                                                                           *
+ *  Christian Krause  <christian.krause@mailbox.org>                         *
+ *                                                                           *
+ * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * *
+ *                                                                           *
+ *  This file is part of strace-analyzer.                                    *
+ *                                                                           *
+ *  strace-analyzer is free software: you can redistribute it and/or modify  *
+ *  it under the terms of the GNU General Public License as published by     *
+ *  the Free Software Foundation, either version 3 of the license, or any    *
+ *  later version.                                                           *
+ *                                                                           *
+ *  strace-analyzer is distributed in the hope that it will be useful, but   *
+ *  WITHOUT ANY WARRANTY; without even the implied warranty of               *
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU         *
+ *  General Public License for more details.                                 *
+ *                                                                           *
+ *  You should have received a copy of the GNU General Public License along  *
+ *  with strace-analyzer. If not, see <http://www.gnu.org/licenses/>.        *
+ *                                                                           *
+ * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * */
+
+//! Filters that decide which files are shown in the report.
+//!
+//! Patterns are globs, e.g. `/tmp/**`, or regexes if prefixed with `re:`,
+//! e.g. `re:^/usr/lib/.*\.so`. A file is shown if it matches any include
+//! pattern or doesn't match any exclude pattern, i.e. includes take
+//! precedence over excludes.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, anyhow};
+use globset::{Glob, GlobMatcher};
+use regex::Regex;
+
+/// Files that are hidden by default: system files, the standard streams and
+/// file descriptors whose file is unknown.
+const DEFAULT_EXCLUDES: &[&str] = &[
+    "/bin/**",
+    "/dev/null",
+    "/etc/**",
+    "/lib/**",
+    "/lib64/**",
+    "/opt/**",
+    "/proc/**",
+    "/run/**",
+    "/sbin/**",
+    "/sys/**",
+    "/tmp/**",
+    "/usr/**",
+    "STDOUT",
+    "STDERR",
+    "STDIN",
+    "SOCKET",
+    "DUP",
+    "PIPE",
+];
+
+/// A glob or regex that is matched against file names.
+#[derive(Clone, Debug)]
+pub enum Pattern {
+    Glob(GlobMatcher),
+    Regex(Regex),
+}
+
+impl Pattern {
+    /// Parses a glob or, if prefixed with `re:`, a regex.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the glob or regex is invalid.
+    pub fn new(pattern: &str) -> Result<Self> {
+        if let Some(regex) = pattern.strip_prefix("re:") {
+            let regex = Regex::new(regex)
+                .with_context(|| format!("invalid regex: {regex}"))?;
+
+            Ok(Self::Regex(regex))
+        } else {
+            let glob = Glob::new(pattern)
+                .with_context(|| format!("invalid glob: {pattern}"))?;
+
+            Ok(Self::Glob(glob.compile_matcher()))
+        }
+    }
+
+    #[must_use]
+    pub fn is_match(&self, file: &str) -> bool {
+        match self {
+            Self::Glob(glob) => glob.is_match(file),
+            Self::Regex(regex) => regex.is_match(file),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Filter {
+    include: Vec<Pattern>,
+    exclude: Vec<Pattern>,
+}
+
+impl Filter {
+    /// Returns a filter that shows all files.
+    #[must_use]
+    pub const fn empty() -> Self {
+        Self {
+            include: vec![],
+            exclude: vec![],
+        }
+    }
+
+    /// Shows files matching `pattern`, even if they are excluded.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the pattern is invalid.
+    pub fn include(&mut self, pattern: &str) -> Result<()> {
+        self.include.push(Pattern::new(pattern)?);
+        Ok(())
+    }
+
+    /// Hides files matching `pattern`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the pattern is invalid.
+    pub fn exclude(&mut self, pattern: &str) -> Result<()> {
+        self.exclude.push(Pattern::new(pattern)?);
+        Ok(())
+    }
+
+    #[must_use]
+    pub fn is_shown(&self, file: &str) -> bool {
+        self.include.iter().any(|pattern| pattern.is_match(file))
+            || !self.exclude.iter().any(|pattern| pattern.is_match(file))
+    }
+
+    /// Parses the filters of a config file, which replace the built-in
+    /// defaults. Each line is either empty, a `#` comment or an `include` or
+    /// `exclude` directive followed by a pattern:
+    ///
+    /// ```text
+    /// # our scratch data lives in /tmp
+    /// include /tmp/**
+    /// exclude /proc/**
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a line or pattern is invalid.
+    pub fn parse(config: &str) -> Result<Self> {
+        let mut filter = Self::empty();
+
+        for (n, line) in config.lines().enumerate() {
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let result = match line.split_once(char::is_whitespace) {
+                Some(("include", pattern)) => filter.include(pattern.trim()),
+                Some(("exclude", pattern)) => filter.exclude(pattern.trim()),
+                _ => Err(anyhow!("expected include or exclude: {line}")),
+            };
+
+            result.with_context(|| format!("line {}", n + 1))?;
+        }
+
+        Ok(filter)
+    }
+
+    /// Loads the filters of a config file, see [`Filter::parse`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can't be read or is invalid.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+
+        fs::read_to_string(path)
+            .map_err(anyhow::Error::from)
+            .and_then(|config| Self::parse(&config))
+            .with_context(|| format!("config file {}", path.display()))
+    }
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        let mut filter = Self::empty();
+
+        for pattern in DEFAULT_EXCLUDES {
+            // UNWRAP: the built-in patterns are valid
+            filter.exclude(pattern).unwrap();
+        }
+
+        filter
+    }
+}
+
+/// Returns the path of the default config file, i.e.
+/// `$XDG_CONFIG_HOME/strace-analyzer/config`, falling back to
+/// `~/.config/strace-analyzer/config`.
+#[must_use]
+pub fn default_config_file() -> Option<PathBuf> {
+    let config_home = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            std::env::var_os("HOME")
+                .map(|home| Path::new(&home).join(".config"))
+        })?;
+
+    Some(config_home.join("strace-analyzer").join("config"))
+}
+
+// ----------------------------------------------------------------------------
+// tests
+// ----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_filter() {
+        let filter = Filter::default();
+
+        assert!(filter.is_shown("data.bin"));
+        assert!(filter.is_shown("/home/user/data.bin"));
+        assert!(filter.is_shown("unix:/run/foo.sock"));
+        assert!(!filter.is_shown("/tmp/scratch/data.bin"));
+        assert!(!filter.is_shown("/proc/self/status"));
+        assert!(!filter.is_shown("STDOUT"));
+    }
+
+    #[test]
+    fn parse_filter() {
+        let mut filter = Filter::parse(
+            "# scratch data\n\
+             include /tmp/scratch/**\n\
+             \n\
+             exclude /tmp/**\n\
+             exclude re:\\.so(\\.\\d+)*$\n",
+        )
+        .unwrap();
+
+        assert!(filter.is_shown("/tmp/scratch/data.bin"));
+        assert!(!filter.is_shown("/tmp/other"));
+        assert!(!filter.is_shown("/usr/lib/libc.so.6"));
+        assert!(filter.is_shown("/proc/self/status"));
+
+        filter.exclude("/proc/**").unwrap();
+        assert!(!filter.is_shown("/proc/self/status"));
+
+        assert!(Filter::parse("show /tmp/**").is_err());
+        assert!(Filter::parse("exclude re:(").is_err());
+    }
+}
@@ -0,0 +1,66 @@
+/* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * *
+ *                                                                           *
+ *  Copyright  (C)  2015-2024  Christian Krause                              *
+ *                                                                           *
+ *  Christian Krause  <christian.krause@mailbox.org>                         *
+ *                                                                           *
+ * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * *
+ *                                                                           *
+ *  This file is part of strace-analyzer.                                    *
+ *                                                                           *
+ *  strace-analyzer is free software: you can redistribute it and/or modify  *
+ *  it under the terms of the GNU General Public License as published by     *
+ *  the Free Software Foundation, either version 3 of the license, or any    *
+ *  later version.                                                           *
+ *                                                                           *
+ *  strace-analyzer is distributed in the hope that it will be useful, but   *
+ *  WITHOUT ANY WARRANTY; without even the implied warranty of               *
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU         *
+ *  General Public License for more details.                                 *
+ *                                                                           *
+ *  You should have received a copy of the GNU General Public License along  *
+ *  with strace-analyzer. If not, see <http://www.gnu.org/licenses/>.        *
+ *                                                                           *
+ * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * */
+
+//! Analyzes strace output.
+//!
+//! The analysis follows the file descriptors of a traced process and reports
+//! the I/O done with each file as a [`Summary`]:
+//!
+//! ```no_run
+//! use strace_analyzer::{Analysis, Config};
+//!
+//! let mut analysis = Analysis::new(Config::default());
+//!
+//! for summary in analysis.summaries("cmd.strace.1234")? {
+//!     println!("{} read {} bytes", summary.file(), summary.read_bytes());
+//! }
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+//!
+//! Single lines of strace output can be parsed with [`Event::parse`], the
+//! syscalls themselves with [`parser::Call::parse`] and [`Syscall::parse`].
+
+#![deny(clippy::all)]
+#![warn(clippy::pedantic, clippy::nursery, clippy::cargo)]
+
+pub mod analysis;
+pub mod compression;
+pub mod config;
+pub mod event;
+pub mod filter;
+mod log;
+pub mod output;
+pub mod parser;
+pub mod process;
+pub mod strace;
+pub mod summary;
+pub mod syscall;
+
+pub use crate::analysis::Analysis;
+pub use crate::config::Config;
+pub use crate::event::Event;
+pub use crate::output::Output;
+pub use crate::summary::Summary;
+pub use crate::syscall::Syscall;
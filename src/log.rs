@@ -25,14 +25,18 @@
 
 use crate::config::Config;
 
-pub fn debug<S: AsRef<str>>(message: S, config: Config) {
+pub fn debug<S: AsRef<str>>(message: S, config: &Config) {
     if config.debug {
         eprintln!("[debug] {}", message.as_ref());
     }
 }
 
-pub fn verbose<S: AsRef<str>>(message: S, config: Config) {
+pub fn verbose<S: AsRef<str>>(message: S, config: &Config) {
     if config.debug || config.verbose {
         eprintln!("{}", message.as_ref());
     }
 }
+
+pub fn warn<S: AsRef<str>>(message: S) {
+    eprintln!("[warning] {}", message.as_ref());
+}
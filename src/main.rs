@@ -26,23 +26,45 @@
 #![deny(clippy::all)]
 #![warn(clippy::pedantic, clippy::nursery, clippy::cargo)]
 
-mod analysis;
 mod cli;
-mod config;
-mod log;
-mod output;
-mod summary;
+
+use std::ffi::OsString;
+use std::process::ExitCode;
+use std::time::Duration;
 
 use anyhow::Result;
 
-use crate::config::Config;
+use strace_analyzer::{Config, analysis, strace};
 
-fn main() -> Result<()> {
+fn main() -> Result<ExitCode> {
     let args = cli::build().get_matches();
     let config = Config::try_from(&args)?;
 
-    // UNWRAP: required via clap
+    if let Some(args) = args.subcommand_matches("run") {
+        let command: Vec<OsString> = args
+            .get_many::<OsString>("command")
+            .into_iter()
+            .flatten()
+            .cloned()
+            .collect();
+
+        return strace::run(&command, args.get_flag("keep"), &config);
+    }
+
+    if let Some(args) = args.subcommand_matches("attach") {
+        // UNWRAP: required via clap
+        let pid = args.get_one::<u32>("pid").copied().unwrap();
+
+        // UNWRAP: default via clap
+        let interval = args.get_one::<u64>("interval").copied().unwrap();
+
+        return strace::attach(pid, Duration::from_secs(interval), &config);
+    }
+
+    // UNWRAP: required via clap without subcommand
     let input = args.get_one::<String>("input").unwrap();
 
-    analysis::run(input, config)
+    analysis::run(input, &config)?;
+
+    Ok(ExitCode::SUCCESS)
 }
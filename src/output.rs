@@ -30,18 +30,42 @@ use clap::ValueEnum;
 use clap::builder::PossibleValue;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum Output {
     Continuous,
+    Csv,
+    Tsv,
     #[cfg(feature = "table")]
     Table,
+    #[cfg(feature = "json")]
+    Json,
+    Tree,
+    Programs,
 }
 
 impl Output {
+    #[must_use]
     pub const fn name(self) -> &'static str {
         match self {
             Self::Continuous => "continuous",
+            Self::Csv => "csv",
+            Self::Tsv => "tsv",
             #[cfg(feature = "table")]
             Self::Table => "table",
+            #[cfg(feature = "json")]
+            Self::Json => "json",
+            Self::Tree => "tree",
+            Self::Programs => "programs",
+        }
+    }
+
+    /// Returns whether the summaries of the same file are merged by default.
+    #[must_use]
+    pub const fn aggregates(self) -> bool {
+        match self {
+            #[cfg(feature = "table")]
+            Self::Table => true,
+            _ => false,
         }
     }
 }
@@ -65,8 +89,14 @@ impl FromStr for Output {
 
         match s {
             "continuous" => Ok(Self::Continuous),
+            "csv" => Ok(Self::Csv),
+            "tsv" => Ok(Self::Tsv),
             #[cfg(feature = "table")]
             "table" => Ok(Self::Table),
+            #[cfg(feature = "json")]
+            "json" => Ok(Self::Json),
+            "tree" => Ok(Self::Tree),
+            "programs" => Ok(Self::Programs),
             _ => Err(anyhow!("invalid output")),
         }
     }
@@ -76,8 +106,14 @@ impl ValueEnum for Output {
     fn value_variants<'a>() -> &'a [Self] {
         &[
             Self::Continuous,
+            Self::Csv,
+            Self::Tsv,
             #[cfg(feature = "table")]
             Self::Table,
+            #[cfg(feature = "json")]
+            Self::Json,
+            Self::Tree,
+            Self::Programs,
         ]
     }
 
@@ -0,0 +1,382 @@
+/* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * *
+ *                                                                           *
+ *  Copyright  (C)  2015-2024  Christian Krause                              *
+ *                                                                           *
+ *  Christian Krause  <christian.krause@mailbox.org>                         *
+ *                                                                           *
+ * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * *
+ *                                                                           *
+ *  This file is part of strace-analyzer.                                    *
+ *                                                                           *
+ *  strace-analyzer is free software: you can redistribute it and/or modify  *
+ *  it under the terms of the GNU General Public License as published by     *
+ *  the Free Software Foundation, either version 3 of the license, or any    *
+ *  later version.                                                           *
+ *                                                                           *
+ *  strace-analyzer is distributed in the hope that it will be useful, but   *
+ *  WITHOUT ANY WARRANTY; without even the implied warranty of               *
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU         *
+ *  General Public License for more details.                                 *
+ *                                                                           *
+ *  You should have received a copy of the GNU General Public License along  *
+ *  with strace-analyzer. If not, see <http://www.gnu.org/licenses/>.        *
+ *                                                                           *
+ * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * */
+
+//! Tokenizer for the syscalls strace prints, e.g.
+//! `read(3, "abc"..., 4096) = 3`.
+//!
+//! Arguments are split at the commas on the top level, i.e. commas within
+//! strings, arrays `[...]`, structs `{...}` and nested calls `(...)` are part
+//! of the argument.
+
+/// A syscall split into its name, arguments and return value.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Call<'a> {
+    pub name: &'a str,
+    pub args: Vec<&'a str>,
+    pub result: Return<'a>,
+}
+
+/// The return value of a syscall.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Return<'a> {
+    /// The syscall succeeded with a non-negative return value.
+    Value(u64),
+
+    /// The syscall failed with an error, e.g. `ENOENT`.
+    Error(&'a str),
+
+    /// The return value is unknown, e.g. `= ?` for `exit_group`.
+    Unknown,
+}
+
+impl<'a> Call<'a> {
+    /// Parses a complete syscall, returns `None` if `call` is not a
+    /// syscall, e.g. a signal or exit notification, or if it is incomplete.
+    #[must_use]
+    pub fn parse(call: &'a str) -> Option<Self> {
+        let open = call.find('(')?;
+        let name = &call[..open];
+
+        if name.is_empty()
+            || !name.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_')
+        {
+            return None;
+        }
+
+        let close = open + closing(&call[open..])?;
+        let args = split(&call[open + 1..close]);
+
+        let result = call[close + 1..].trim_start().strip_prefix('=')?;
+        let result = Return::parse(result.trim_start());
+
+        Some(Self { name, args, result })
+    }
+
+    /// Returns the argument at `index`.
+    #[must_use]
+    pub fn arg(&self, index: usize) -> Option<&'a str> {
+        self.args.get(index).copied()
+    }
+}
+
+impl<'a> Return<'a> {
+    fn parse(result: &'a str) -> Self {
+        let mut tokens = result.split_whitespace();
+
+        let Some(value) = tokens.next() else {
+            return Self::Unknown;
+        };
+
+        if let Some(value) = number(value) {
+            return Self::Value(value);
+        }
+
+        match tokens.next() {
+            Some(error) if error.starts_with('E') => Self::Error(error),
+            _ => Self::Unknown,
+        }
+    }
+
+    /// Returns the return value, if the syscall succeeded.
+    #[must_use]
+    pub const fn value(self) -> Option<u64> {
+        match self {
+            Self::Value(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+/// Parses a decimal or hexadecimal number.
+#[must_use]
+pub fn number(s: &str) -> Option<u64> {
+    s.strip_prefix("0x").map_or_else(
+        || s.parse().ok(),
+        |hex| u64::from_str_radix(hex, 16).ok(),
+    )
+}
+
+/// Returns the elements of an array, e.g. `[3, 4]`.
+#[must_use]
+pub fn elements(array: &str) -> Option<Vec<&str>> {
+    if !array.starts_with('[') {
+        return None;
+    }
+
+    let close = closing(array).unwrap_or(array.len());
+
+    Some(split(&array[1..close]))
+}
+
+/// Returns the fields of a struct, e.g. `{sa_family=AF_UNIX, sun_path=""}`,
+/// as key value pairs. Fields without key, like the `inet_pton` call in IPv6
+/// socket addresses, have an empty key.
+#[must_use]
+pub fn fields(object: &str) -> Option<Vec<(&str, &str)>> {
+    if !object.starts_with('{') {
+        return None;
+    }
+
+    let close = closing(object).unwrap_or(object.len());
+
+    let fields = split(&object[1..close])
+        .into_iter()
+        .map(|field| {
+            field
+                .split_once('=')
+                .filter(|(key, _)| {
+                    key.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_')
+                })
+                .unwrap_or(("", field))
+        })
+        .collect();
+
+    Some(fields)
+}
+
+/// Returns the value of the field `key` of a struct.
+#[must_use]
+pub fn field<'a>(object: &'a str, key: &str) -> Option<&'a str> {
+    fields(object)?
+        .into_iter()
+        .find_map(|(k, value)| (k == key).then_some(value))
+}
+
+/// Returns the arguments of a function call within an argument, e.g. the
+/// port of `htons(53)`.
+#[must_use]
+pub fn function_args<'a>(
+    value: &'a str,
+    function: &str,
+) -> Option<Vec<&'a str>> {
+    let args = value.strip_prefix(function)?;
+
+    if !args.starts_with('(') {
+        return None;
+    }
+
+    let close = closing(args)?;
+
+    Some(split(&args[1..close]))
+}
+
+/// Returns the contents of a quoted string with its escapes resolved, e.g.
+/// `"foo\"bar"` becomes `foo"bar`. Truncated strings, i.e. `"foo"...`, are
+/// returned without the dots.
+#[must_use]
+pub fn unquote(quoted: &str) -> Option<String> {
+    let quoted = quoted.strip_prefix('"')?;
+
+    let mut bytes = vec![];
+    let mut chars = quoted.char_indices();
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => {
+                let rest = &quoted[i + 1..];
+                return (rest.is_empty() || rest == "...")
+                    .then(|| String::from_utf8_lossy(&bytes).into_owned());
+            }
+
+            '\\' => {
+                let (_, escaped) = chars.next()?;
+
+                let byte = match escaped {
+                    'n' => b'\n',
+                    't' => b'\t',
+                    'r' => b'\r',
+                    'v' => 0x0b,
+                    'f' => 0x0c,
+                    'x' => {
+                        let hex = quoted.get(i + 2..i + 4)?;
+                        chars.nth(1);
+                        u8::from_str_radix(hex, 16).ok()?
+                    }
+                    '0'..='7' => {
+                        let start = i + 1;
+                        let digits = quoted[start..]
+                            .bytes()
+                            .take(3)
+                            .take_while(|b| matches!(b, b'0'..=b'7'))
+                            .count();
+
+                        for _ in 1..digits {
+                            chars.next();
+                        }
+
+                        let octal = &quoted[start..start + digits];
+                        u8::from_str_radix(octal, 8).ok()?
+                    }
+                    c => {
+                        let mut buf = [0; 4];
+                        bytes.extend(c.encode_utf8(&mut buf).as_bytes());
+                        continue;
+                    }
+                };
+
+                bytes.push(byte);
+            }
+
+            c => {
+                let mut buf = [0; 4];
+                bytes.extend(c.encode_utf8(&mut buf).as_bytes());
+            }
+        }
+    }
+
+    None
+}
+
+/// Returns the index of the bracket that closes the one `s` starts with.
+fn closing(s: &str) -> Option<usize> {
+    Structure::new(s)
+        .find(|(_, _, depth)| *depth == 0)
+        .map(|(i, _, _)| i)
+}
+
+/// Splits a list at the commas on the top level.
+fn split(list: &str) -> Vec<&str> {
+    let mut items = vec![];
+    let mut start = 0;
+
+    for (i, b, depth) in Structure::new(list) {
+        if b == b',' && depth == 0 {
+            items.push(list[start..i].trim());
+            start = i + 1;
+        }
+    }
+
+    let item = list[start..].trim();
+
+    if !item.is_empty() || !items.is_empty() {
+        items.push(item);
+    }
+
+    items
+}
+
+/// Iterates over the bytes of strace output that are not quoted, together
+/// with the nesting depth of brackets after the byte.
+struct Structure<'a> {
+    bytes: std::iter::Enumerate<std::str::Bytes<'a>>,
+    depth: usize,
+}
+
+impl<'a> Structure<'a> {
+    fn new(s: &'a str) -> Self {
+        Self {
+            bytes: s.bytes().enumerate(),
+            depth: 0,
+        }
+    }
+}
+
+impl Iterator for Structure<'_> {
+    type Item = (usize, u8, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (i, b) = self.bytes.next()?;
+
+        match b {
+            b'"' => {
+                // skip the string, including escaped quotes
+                while let Some((_, b)) = self.bytes.next() {
+                    match b {
+                        b'\\' => {
+                            self.bytes.next();
+                        }
+                        b'"' => break,
+                        _ => {}
+                    }
+                }
+            }
+
+            b'(' | b'[' | b'{' => self.depth += 1,
+
+            b')' | b']' | b'}' => self.depth = self.depth.saturating_sub(1),
+
+            _ => {}
+        }
+
+        Some((i, b, self.depth))
+    }
+}
+
+// ----------------------------------------------------------------------------
+// tests
+// ----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_call() {
+        let call = Call::parse(r#"read(3, "foo, 42)\" = 1"..., 4096) = 4096"#)
+            .unwrap();
+
+        assert_eq!(call.name, "read");
+        assert_eq!(call.args, vec!["3", r#""foo, 42)\" = 1"..."#, "4096"]);
+        assert_eq!(call.result, Return::Value(4096));
+
+        let call = Call::parse(
+            r#"openat(AT_FDCWD, "/etc/foo", O_RDONLY) = -1 ENOENT (No such file or directory)"#,
+        )
+        .unwrap();
+
+        assert_eq!(call.args, vec!["AT_FDCWD", r#""/etc/foo""#, "O_RDONLY"]);
+        assert_eq!(call.result, Return::Error("ENOENT"));
+
+        let call = Call::parse(
+            "readv(3, [{iov_base=NULL, iov_len=1}, {iov_base=NULL, iov_len=2}], 2) = 3",
+        )
+        .unwrap();
+
+        let iov = elements(call.arg(1).unwrap()).unwrap();
+        assert_eq!(iov.len(), 2);
+        assert_eq!(field(iov[1], "iov_len"), Some("2"));
+
+        assert_eq!(Call::parse("exit_group(0) = ?").unwrap().args, vec!["0"]);
+        assert_eq!(Call::parse("getpid() = 42").unwrap().args.len(), 0);
+        assert_eq!(Call::parse("+++ exited with 0 +++"), None);
+        assert_eq!(Call::parse("read(3, "), None);
+    }
+
+    #[test]
+    fn parse_strings() {
+        assert_eq!(unquote(r#""foo""#).as_deref(), Some("foo"));
+        assert_eq!(unquote(r#"""..."#).as_deref(), Some(""));
+        assert_eq!(
+            unquote(r#""a,b \"c\".bin""#).as_deref(),
+            Some(r#"a,b "c".bin"#)
+        );
+        assert_eq!(
+            unquote(r#""tab\there\n""#).as_deref(),
+            Some("tab\there\n")
+        );
+        assert_eq!(unquote(r#""\x41\101\0""#).as_deref(), Some("AA\0"));
+        assert_eq!(unquote("foo"), None);
+    }
+}
@@ -0,0 +1,442 @@
+/* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * *
+ *                                                                           *
+ *  Copyright  (C)  2015-2024  Christian Krause                              *
+ *                                                                           *
+ *  Christian Krause  <christian.krause@mailbox.org>                         *
+ *                                                                           *
+ * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * *
+ *                                                                           *
+ *  This file is part of strace-analyzer.                                    *
+ *                                                                           *
+ *  strace-analyzer is free software: you can redistribute it and/or modify  *
+ *  it under the terms of the GNU General Public License as published by     *
+ *  the Free Software Foundation, either version 3 of the license, or any    *
+ *  later version.                                                           *
+ *                                                                           *
+ *  strace-analyzer is distributed in the hope that it will be useful, but   *
+ *  WITHOUT ANY WARRANTY; without even the implied warranty of               *
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU         *
+ *  General Public License for more details.                                 *
+ *                                                                           *
+ *  You should have received a copy of the GNU General Public License along  *
+ *  with strace-analyzer. If not, see <http://www.gnu.org/licenses/>.        *
+ *                                                                           *
+ * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * */
+
+//! The tree of the traced processes.
+//!
+//! Processes are recorded when they are created with `clone`, `fork` and
+//! friends, together with the program and arguments of their last
+//! `execve`.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use crate::config::Config;
+use crate::summary::{Summary, aggregate, humanize};
+
+/// A traced process.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Process {
+    pub pid: Option<u32>,
+
+    /// The process that created this one, `None` for the traced process.
+    pub parent: Option<u32>,
+
+    /// The path of the program of the last `execve`, the one of the parent
+    /// until the process executes a program itself.
+    pub program: Option<String>,
+
+    /// The arguments of the last `execve`, including the program name.
+    pub args: Vec<String>,
+}
+
+impl Process {
+    /// Returns the command line of the program, see [`command_line`].
+    #[must_use]
+    pub fn command(&self) -> Option<String> {
+        self.program
+            .as_deref()
+            .map(|program| command_line(program, &self.args))
+    }
+}
+
+/// The traced processes, keyed by PID. The PID of the traced process itself
+/// may be unknown, e.g. in a trace of a single process without `-f`.
+#[derive(Clone, Debug, Default)]
+pub struct Tree {
+    processes: BTreeMap<Option<u32>, Process>,
+}
+
+impl Tree {
+    /// Records that `parent` has created the process `pid`.
+    pub fn spawn(&mut self, parent: Option<u32>, pid: u32) {
+        let (program, args) = self
+            .processes
+            .get(&parent)
+            .map(|parent| (parent.program.clone(), parent.args.clone()))
+            .unwrap_or_default();
+
+        let process = self.entry(Some(pid));
+        process.parent = parent;
+
+        // the child may have executed a program before its parent's clone
+        // syscall returned, i.e. with vfork
+        if process.program.is_none() {
+            process.program = program;
+            process.args = args;
+        }
+    }
+
+    /// Records the program that the process `pid` executes.
+    pub fn exec(
+        &mut self,
+        pid: Option<u32>,
+        program: String,
+        args: Vec<String>,
+    ) {
+        let process = self.entry(pid);
+        process.program = Some(program);
+        process.args = args;
+    }
+
+    /// Returns the process `pid`.
+    #[must_use]
+    pub fn get(&self, pid: Option<u32>) -> Option<&Process> {
+        self.processes.get(&pid)
+    }
+
+    /// Returns all processes, ordered by PID.
+    pub fn processes(&self) -> impl Iterator<Item = &Process> {
+        self.processes.values()
+    }
+
+    /// Returns whether the process `pid` is `ancestor` or one of its
+    /// descendants.
+    #[must_use]
+    pub fn descends(&self, pid: Option<u32>, ancestor: u32) -> bool {
+        let mut pid = pid;
+
+        // bounded, in case reused PIDs form a cycle
+        for _ in 0..=self.processes.len() {
+            match pid {
+                Some(current) if current == ancestor => return true,
+                Some(_) => {
+                    pid = self.processes.get(&pid).and_then(|p| p.parent);
+                }
+                None => return false,
+            }
+        }
+
+        false
+    }
+
+    fn entry(&mut self, pid: Option<u32>) -> &mut Process {
+        self.processes.entry(pid).or_insert_with(|| Process {
+            pid,
+            ..Process::default()
+        })
+    }
+}
+
+/// Returns the command line of an `execve`, i.e. the program followed by
+/// its arguments. Consecutive truncated arguments are shown as a single
+/// `...`.
+#[must_use]
+pub fn command_line(program: &str, args: &[String]) -> String {
+    let mut command = String::from(program);
+    let mut truncated = false;
+
+    for arg in args.iter().skip(1) {
+        if arg == "..." {
+            if truncated {
+                continue;
+            }
+
+            truncated = true;
+        } else {
+            truncated = false;
+        }
+
+        command.push(' ');
+        command.push_str(arg);
+    }
+
+    command
+}
+
+/// Returns the name of a program, i.e. the file name of its path.
+#[must_use]
+pub fn program_name(program: &str) -> &str {
+    Path::new(program)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or(program)
+}
+
+/// Returns whether `program` is the program `name`, i.e. has this path or,
+/// if `name` has no slash, this file name.
+#[must_use]
+pub fn is_program(program: &str, name: &str) -> bool {
+    if name.contains('/') {
+        program == name
+    } else {
+        program_name(program) == name
+    }
+}
+
+/// Shows the summaries grouped by the name of the program that did the I/O.
+/// Each program is shown with the number of its processes, its I/O and its
+/// summaries per file.
+pub fn show_programs(summaries: Vec<Summary>, config: &Config) {
+    let mut programs: BTreeMap<String, Vec<Summary>> = BTreeMap::new();
+
+    for summary in summaries {
+        let name = summary.program.as_deref().map_or("?", program_name);
+        programs
+            .entry(String::from(name))
+            .or_default()
+            .push(summary);
+    }
+
+    for (name, summaries) in programs {
+        let processes = summaries
+            .iter()
+            .map(|summary| summary.pid)
+            .collect::<BTreeSet<_>>()
+            .len();
+
+        let summaries = aggregate(summaries);
+        let (read, write) = io(&summaries);
+
+        println!(
+            "{name}: {processes} {}, read {}, write {}",
+            if processes == 1 {
+                "process"
+            } else {
+                "processes"
+            },
+            humanize(read),
+            humanize(write)
+        );
+
+        for summary in &summaries {
+            if summary.should_show(config) {
+                for line in summary.lines() {
+                    println!("  {line}");
+                }
+            }
+        }
+    }
+}
+
+/// Shows the processes as a tree. Each process is shown with its I/O, the
+/// I/O together with its descendants and its summaries per file. With
+/// `--pid`, the tree starts at that process.
+pub fn show_tree(summaries: Vec<Summary>, tree: &Tree, config: &Config) {
+    let mut tree = tree.clone();
+    let mut files: BTreeMap<Option<u32>, Vec<Summary>> = BTreeMap::new();
+
+    for summary in summaries {
+        files.entry(summary.pid).or_default().push(summary);
+    }
+
+    for (pid, summaries) in &mut files {
+        tree.entry(*pid);
+        *summaries = aggregate(std::mem::take(summaries));
+    }
+
+    let mut children: BTreeMap<Option<u32>, Vec<Option<u32>>> =
+        BTreeMap::new();
+
+    let mut roots = vec![];
+
+    for process in tree.processes() {
+        match process.parent {
+            Some(parent) if tree.get(Some(parent)).is_some() => {
+                children.entry(Some(parent)).or_default().push(process.pid);
+            }
+
+            _ => roots.push(process.pid),
+        }
+    }
+
+    if let Some(pid) = config.pid {
+        roots = vec![Some(pid)];
+    }
+
+    let view = View {
+        tree: &tree,
+        children,
+        files,
+        config,
+    };
+
+    for pid in roots {
+        view.show(pid, "", "", "");
+    }
+}
+
+/// The processes together with their children and summaries.
+struct View<'a> {
+    tree: &'a Tree,
+    children: BTreeMap<Option<u32>, Vec<Option<u32>>>,
+    files: BTreeMap<Option<u32>, Vec<Summary>>,
+    config: &'a Config,
+}
+
+impl View<'_> {
+    fn show(
+        &self,
+        pid: Option<u32>,
+        prefix: &str,
+        branch: &str,
+        indent: &str,
+    ) {
+        let children = self.children.get(&pid).map_or(&[][..], Vec::as_slice);
+        let files = self.files.get(&pid).map_or(&[][..], Vec::as_slice);
+
+        let (read, write) = io(files);
+
+        let command = self
+            .tree
+            .get(pid)
+            .and_then(Process::command)
+            .map_or_else(String::new, |command| format!(" {command}"));
+
+        let descendants = if children.is_empty() {
+            String::new()
+        } else {
+            let (read, write) = self.total(pid);
+
+            format!(
+                ", with descendants read {}, write {}",
+                humanize(read),
+                humanize(write)
+            )
+        };
+
+        println!(
+            "{prefix}{branch}{}{command}: read {}, write {}{descendants}",
+            pid.map_or_else(|| String::from("?"), |pid| pid.to_string()),
+            humanize(read),
+            humanize(write)
+        );
+
+        let prefix = format!("{prefix}{indent}");
+        let bar = if children.is_empty() { "  " } else { "│ " };
+
+        for summary in files {
+            if summary.should_show(self.config) {
+                for line in summary.lines() {
+                    println!("{prefix}{bar}  {line}");
+                }
+            }
+        }
+
+        for (i, child) in children.iter().enumerate() {
+            if i + 1 == children.len() {
+                self.show(*child, &prefix, "└── ", "    ");
+            } else {
+                self.show(*child, &prefix, "├── ", "│   ");
+            }
+        }
+    }
+
+    /// Returns the bytes read and written by `pid` and its descendants.
+    fn total(&self, pid: Option<u32>) -> (u64, u64) {
+        let files = self.files.get(&pid).map_or(&[][..], Vec::as_slice);
+
+        self.children
+            .get(&pid)
+            .into_iter()
+            .flatten()
+            .map(|child| self.total(*child))
+            .fold(io(files), |(read, write), (r, w)| (read + r, write + w))
+    }
+}
+
+/// Returns the bytes read and written in `summaries`.
+fn io(summaries: &[Summary]) -> (u64, u64) {
+    summaries.iter().fold((0, 0), |(read, write), summary| {
+        (read + summary.read_bytes, write + summary.write_bytes)
+    })
+}
+
+// ----------------------------------------------------------------------------
+// tests
+// ----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn process_tree() {
+        let mut tree = Tree::default();
+
+        tree.exec(
+            Some(100),
+            String::from("/usr/bin/make"),
+            vec![String::from("make"), String::from("all")],
+        );
+        tree.spawn(Some(100), 101);
+        tree.spawn(Some(101), 102);
+        tree.exec(Some(102), String::from("/usr/bin/gcc"), vec![]);
+
+        assert_eq!(
+            tree.get(Some(101)),
+            Some(&Process {
+                pid: Some(101),
+                parent: Some(100),
+                program: Some(String::from("/usr/bin/make")),
+                args: vec![String::from("make"), String::from("all")],
+            })
+        );
+
+        assert_eq!(
+            tree.get(Some(101)).and_then(Process::command).as_deref(),
+            Some("/usr/bin/make all")
+        );
+
+        assert_eq!(
+            tree.get(Some(102)).and_then(Process::command).as_deref(),
+            Some("/usr/bin/gcc")
+        );
+
+        assert!(tree.descends(Some(102), 100));
+        assert!(tree.descends(Some(101), 101));
+        assert!(!tree.descends(Some(100), 101));
+        assert!(!tree.descends(None, 100));
+    }
+
+    #[test]
+    fn command_lines() {
+        let args = |args: &[&str]| {
+            args.iter()
+                .map(|arg| String::from(*arg))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            command_line("/bin/ls", &args(&["ls", "-l", "/tmp"])),
+            "/bin/ls -l /tmp"
+        );
+
+        assert_eq!(
+            command_line("/bin/ls", &args(&["...", "...", "..."])),
+            "/bin/ls ..."
+        );
+    }
+
+    #[test]
+    fn program_names() {
+        assert_eq!(program_name("/usr/bin/gcc"), "gcc");
+        assert_eq!(program_name("gcc"), "gcc");
+
+        assert!(is_program("/usr/bin/gcc", "gcc"));
+        assert!(is_program("/usr/bin/gcc", "/usr/bin/gcc"));
+        assert!(!is_program("/usr/bin/gcc", "/bin/gcc"));
+        assert!(!is_program("/usr/bin/gcc-13", "gcc"));
+    }
+}
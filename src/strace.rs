@@ -0,0 +1,460 @@
+/* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * *
+ *                                                                           *
+ *  Copyright  (C)  2015-2024  Christian Krause                              *
+ *                                                                           *
+ *  Christian Krause  <christian.krause@mailbox.org>                         *
+ *                                                                           *
+ * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * *
+ *                                                                           *
+ *  This file is part of strace-analyzer.                                    *
+ *                                                                           *
+ *  strace-analyzer is free software: you can redistribute it and/or modify  *
+ *  it under the terms of the GNU General Public License as published by     *
+ *  the Free Software Foundation, either version 3 of the license, or any    *
+ *  later version.                                                           *
+ *                                                                           *
+ *  strace-analyzer is distributed in the hope that it will be useful, but   *
+ *  WITHOUT ANY WARRANTY; without even the implied warranty of               *
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU         *
+ *  General Public License for more details.                                 *
+ *                                                                           *
+ *  You should have received a copy of the GNU General Public License along  *
+ *  with strace-analyzer. If not, see <http://www.gnu.org/licenses/>.        *
+ *                                                                           *
+ * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * */
+
+//! Tracing commands with strace.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ffi::{OsStr, OsString};
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader};
+use std::os::unix::process::ExitStatusExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitCode, ExitStatus, Stdio};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result, anyhow};
+use rustix::process::{Pid, Signal, kill_process};
+use signal_hook::consts::{SIGINT, SIGTERM};
+
+use crate::analysis::{self, Analysis, Trace};
+use crate::config::Config;
+use crate::event::Event;
+use crate::log;
+use crate::parser::Call;
+use crate::summary::Summary;
+use crate::syscall::Syscall;
+
+/// How often an interrupt is checked for while waiting for strace.
+const TICK: Duration = Duration::from_millis(100);
+
+/// Traces `command` with `strace -s 0 -ff` into a temporary directory,
+/// analyzes the traces once it exits and returns its exit status. The
+/// traces are removed afterwards, unless `keep` is set.
+///
+/// On SIGINT or SIGTERM while the command runs, the partial traces are
+/// analyzed and the status is the one of the signal, like a shell reports
+/// it.
+///
+/// # Errors
+///
+/// Returns an error if strace can't be run or if the traces can't be
+/// analyzed.
+pub fn run(
+    command: &[OsString],
+    keep: bool,
+    config: &Config,
+) -> Result<ExitCode> {
+    let program = command.first().context("no command to trace")?;
+    let program = Path::new(program).file_name().unwrap_or(program);
+
+    let dir = tempfile::Builder::new()
+        .prefix("strace-analyzer.")
+        .tempdir()
+        .context("failed to create directory for the traces")?;
+
+    let mut name = program.to_os_string();
+    name.push(".strace");
+    let output = dir.path().join(name);
+
+    log::verbose(
+        format!("[run] tracing into {} ...", dir.path().display()),
+        config,
+    );
+
+    let interrupted = Arc::new(AtomicBool::new(false));
+    let terminated = Arc::new(AtomicBool::new(false));
+
+    let handlers = [
+        signal_hook::flag::register(SIGINT, Arc::clone(&interrupted))?,
+        signal_hook::flag::register(SIGTERM, Arc::clone(&terminated))?,
+    ];
+
+    let mut strace = Command::new("strace")
+        .args(["-s", "0", "-ff", "-o"])
+        .arg(&output)
+        .arg("--")
+        .args(command)
+        .spawn()
+        .context("failed to run strace, is it installed?")?;
+
+    // the terminal sends SIGINT to strace and the command as well, SIGTERM
+    // is passed on to strace, which takes the command down with it
+    let mut forwarded = false;
+
+    let status = loop {
+        if let Some(status) = strace.try_wait()? {
+            break status;
+        }
+
+        if !forwarded && terminated.load(Ordering::Relaxed) {
+            let _ = kill_process(Pid::from_child(&strace), Signal::TERM);
+            forwarded = true;
+        }
+
+        thread::sleep(TICK);
+    };
+
+    // signals during the analysis take the default action again
+    for handler in handlers {
+        signal_hook::low_level::unregister(handler);
+    }
+
+    let signal = if terminated.load(Ordering::Relaxed) {
+        Some(SIGTERM)
+    } else if interrupted.load(Ordering::Relaxed) {
+        Some(SIGINT)
+    } else {
+        None
+    };
+
+    // without traces, e.g. if the command doesn't exist, strace already
+    // reported why
+    let analyzed = root_trace(&output, program).and_then(|trace| {
+        trace.map_or(Ok(()), |trace| analysis::run(&trace, config))
+    });
+
+    if keep {
+        let dir = dir.keep();
+        eprintln!("traces kept in {}", dir.display());
+    }
+
+    analyzed?;
+
+    Ok(ExitCode::from(
+        signal.map_or_else(|| exit_code(status), signal_code),
+    ))
+}
+
+/// Attaches `strace -s 0 -f` to the running process `pid`, reports the
+/// summaries every `interval` and once more when strace detaches, i.e. when
+/// the process exits or on SIGINT.
+///
+/// # Errors
+///
+/// Returns an error if strace can't be run, if the trace can't be read or
+/// if the report can't be written.
+pub fn attach(
+    pid: u32,
+    interval: Duration,
+    config: &Config,
+) -> Result<ExitCode> {
+    let interrupted = Arc::new(AtomicBool::new(false));
+
+    for signal in [SIGINT, SIGTERM] {
+        signal_hook::flag::register(signal, Arc::clone(&interrupted))?;
+    }
+
+    // the standard output of strace is free, the one of the process isn't
+    // redirected when attaching
+    let mut strace = Command::new("strace")
+        .args(["-s", "0", "-f", "-o", "/dev/stdout", "-p"])
+        .arg(pid.to_string())
+        .stdout(Stdio::piped())
+        .spawn()
+        .context("failed to run strace, is it installed?")?;
+
+    let stdout = strace.stdout.take().context("no output of strace")?;
+
+    let (sender, receiver) = mpsc::channel();
+
+    let reader = thread::spawn(move || -> io::Result<()> {
+        for line in BufReader::new(stdout).lines() {
+            if sender.send(line?).is_err() {
+                break;
+            }
+        }
+
+        Ok(())
+    });
+
+    let mut analysis = Analysis::running(pid, config.clone());
+    let mut trace = Trace::new(&mut analysis, None);
+
+    // strace -f traces the existing threads as well, they share the file
+    // descriptors read from /proc
+    for tid in threads(pid) {
+        trace.thread(tid);
+    }
+
+    let closed = RefCell::new(Closed::default());
+    let collect = |summary| closed.borrow_mut().add(summary);
+
+    let start = Instant::now();
+    let mut next = start + interval;
+
+    loop {
+        if interrupted.load(Ordering::Relaxed) {
+            // strace detaches from the process on SIGTERM, it may have done
+            // so already if it got the SIGINT from the terminal as well
+            let _ = kill_process(Pid::from_child(&strace), Signal::TERM);
+            break;
+        }
+
+        let timeout = next.saturating_duration_since(Instant::now());
+
+        match receiver.recv_timeout(timeout.min(TICK)) {
+            Ok(line) => trace.analyze(&line, collect)?,
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+
+        if Instant::now() >= next {
+            log::verbose(
+                format!(
+                    "[attach] snapshot after {}s",
+                    start.elapsed().as_secs()
+                ),
+                config,
+            );
+
+            let mut summaries = closed.borrow().summaries.clone();
+            summaries.extend(trace.snapshot());
+            analysis::report(summaries, &trace.tree(), config)?;
+
+            next += interval;
+        }
+    }
+
+    let status = strace.wait()?;
+
+    reader
+        .join()
+        .map_err(|_| anyhow!("reading the trace failed"))??;
+
+    // analyze what strace has written until it detached
+    for line in receiver.try_iter() {
+        trace.analyze(&line, collect)?;
+    }
+
+    trace.flush(collect);
+    analysis::report(closed.into_inner().summaries, &trace.tree(), config)?;
+
+    if interrupted.load(Ordering::Relaxed) {
+        Ok(ExitCode::SUCCESS)
+    } else {
+        Ok(ExitCode::from(exit_code(status)))
+    }
+}
+
+/// The summaries of the file descriptors closed while attached, merged per
+/// process, program and file, so that a service that opens files over and
+/// over again doesn't grow them without bound.
+#[derive(Default)]
+struct Closed {
+    summaries: Vec<Summary>,
+    index: HashMap<(Option<u32>, Option<String>, String), usize>,
+}
+
+impl Closed {
+    fn add(&mut self, summary: Summary) {
+        let key = (summary.pid, summary.program.clone(), summary.file.clone());
+
+        if let Some(i) = self.index.get(&key) {
+            self.summaries[*i].merge(&summary);
+        } else {
+            self.index.insert(key, self.summaries.len());
+            self.summaries.push(summary);
+        }
+    }
+}
+
+/// Returns the IDs of the threads of the running process `pid`.
+fn threads(pid: u32) -> Vec<u32> {
+    match fs::read_dir(format!("/proc/{pid}/task")) {
+        Ok(entries) => entries
+            .flatten()
+            .filter_map(|entry| entry.file_name().to_str()?.parse().ok())
+            .collect(),
+
+        Err(error) => {
+            log::warn(format!("can't read the threads of {pid}: {error}"));
+            vec![]
+        }
+    }
+}
+
+/// Returns the trace of the traced command itself, i.e. the one with the
+/// lowest PID that starts with the execve of `program`, falling back to the
+/// lowest PID. Only the first line of each trace is read. The traces are
+/// named after the `output` file name with the PID appended.
+fn root_trace(output: &Path, program: &OsStr) -> Result<Option<PathBuf>> {
+    let Some(dir) = output.parent() else {
+        return Ok(None);
+    };
+
+    let mut traces: Vec<(u32, PathBuf)> = vec![];
+
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+
+        if path.file_stem() != output.file_name() {
+            continue;
+        }
+
+        let pid = path
+            .extension()
+            .and_then(|extension| extension.to_str()?.parse().ok());
+
+        if let Some(pid) = pid {
+            traces.push((pid, path));
+        }
+    }
+
+    traces.sort_unstable();
+
+    for (_, trace) in &traces {
+        let mut line = String::new();
+        BufReader::new(File::open(trace)?).read_line(&mut line)?;
+
+        let event = Event::parse(line.trim_end());
+        let syscall =
+            Call::parse(event.call).as_ref().and_then(Syscall::parse);
+
+        let execs = matches!(
+            syscall,
+            Some(Syscall::Exec { program: exec, .. })
+                if Path::new(&exec).file_name() == Some(program)
+        );
+
+        if execs {
+            return Ok(Some(trace.clone()));
+        }
+    }
+
+    Ok(traces.into_iter().next().map(|(_, trace)| trace))
+}
+
+/// Returns the exit code of a process like a shell does, i.e. 128 plus the
+/// signal number if it was killed by a signal.
+fn exit_code(status: ExitStatus) -> u8 {
+    status.code().map_or_else(
+        || status.signal().map_or(1, signal_code),
+        |code| u8::try_from(code).unwrap_or(1),
+    )
+}
+
+/// Returns the exit code of a process killed by `signal`.
+fn signal_code(signal: i32) -> u8 {
+    u8::try_from(128 + signal).unwrap_or(1)
+}
+
+// ----------------------------------------------------------------------------
+// tests
+// ----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use assert_fs::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn find_root_trace() {
+        let temp = assert_fs::TempDir::new().unwrap();
+
+        // a child whose PID is lower, because the PIDs wrapped around
+        temp.child("cmd.strace.99")
+            .write_str("close(3) = 0\n")
+            .unwrap();
+
+        temp.child("cmd.strace.100")
+            .write_str(
+                "execve(\"/usr/bin/cmd\", [\"cmd\"], 0x7ffd /* 2 vars */) = 0\n",
+            )
+            .unwrap();
+
+        temp.child("cmd.strace.101")
+            .write_str(
+                "execve(\"/usr/bin/cmd\", [\"cmd\"], 0x7ffd /* 2 vars */) = 0\n",
+            )
+            .unwrap();
+
+        temp.child("other.strace.1").write_str("").unwrap();
+
+        let output = temp.child("cmd.strace");
+
+        assert_eq!(
+            root_trace(output.path(), OsStr::new("cmd")).unwrap(),
+            Some(temp.child("cmd.strace.100").to_path_buf())
+        );
+
+        // falls back to the lowest PID if no trace starts with the execve
+        assert_eq!(
+            root_trace(output.path(), OsStr::new("other")).unwrap(),
+            Some(temp.child("cmd.strace.99").to_path_buf())
+        );
+
+        temp.close().unwrap();
+    }
+
+    #[test]
+    fn merge_closed() {
+        let mut closed = Closed::default();
+
+        for pid in [100, 101, 100] {
+            closed.add(Summary {
+                pid: Some(pid),
+                read_bytes: 10,
+                opens: 1,
+                ..Summary::new("/etc/hosts")
+            });
+        }
+
+        assert_eq!(
+            closed.summaries,
+            [
+                Summary {
+                    pid: Some(100),
+                    read_bytes: 20,
+                    opens: 2,
+                    ..Summary::new("/etc/hosts")
+                },
+                Summary {
+                    pid: Some(101),
+                    read_bytes: 10,
+                    opens: 1,
+                    ..Summary::new("/etc/hosts")
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn exit_codes() {
+        assert_eq!(exit_code(ExitStatus::from_raw(0)), 0);
+        assert_eq!(exit_code(ExitStatus::from_raw(3 << 8)), 3);
+
+        // killed by SIGINT or SIGKILL
+        assert_eq!(exit_code(ExitStatus::from_raw(SIGINT)), 130);
+        assert_eq!(exit_code(ExitStatus::from_raw(9)), 137);
+
+        assert_eq!(signal_code(SIGTERM), 143);
+    }
+}
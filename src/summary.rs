@@ -23,179 +23,854 @@
  *                                                                           *
  * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * */
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
 
 use bytesize::ByteSize;
 
+#[cfg(feature = "json")]
+use anyhow::Result;
+
+#[cfg(feature = "json")]
+use serde::Serialize;
+
 #[cfg(feature = "table")]
 use prettytable::{Row, Table, cell, format::FormatBuilder};
 
 use crate::config::Config;
 use crate::log::debug;
 
+/// The I/O done with a single file, or with a single file descriptor unless
+/// the summaries are aggregated.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
 pub struct Summary {
-    pub file: String,
-    pub read_freq: HashMap<u64, u64>,
-    pub write_freq: HashMap<u64, u64>,
-    pub read_bytes: u64,
-    pub write_bytes: u64,
+    /// PID of the process that did the I/O, if known.
+    pub(crate) pid: Option<u32>,
+
+    /// Path of the program that the process executed, if known.
+    pub(crate) program: Option<String>,
+
+    pub(crate) file: String,
+    /// Frequencies of the requested buffer sizes of reads.
+    pub(crate) read_freq: HashMap<u64, u64>,
+
+    /// Frequencies of the requested buffer sizes of writes.
+    pub(crate) write_freq: HashMap<u64, u64>,
+
+    /// Frequencies of the bytes actually returned by reads.
+    pub(crate) read_returned: HashMap<u64, u64>,
+
+    /// Frequencies of the bytes actually returned by writes.
+    pub(crate) write_returned: HashMap<u64, u64>,
+
+    pub(crate) read_bytes: u64,
+    pub(crate) write_bytes: u64,
+    pub(crate) read_time: Option<Timing>,
+    pub(crate) write_time: Option<Timing>,
+
+    /// Bytes read via zero-copy syscalls like `sendfile` or `splice`.
+    pub(crate) read_zero_copy: u64,
+
+    /// Bytes written via zero-copy syscalls like `sendfile` or `splice`.
+    pub(crate) write_zero_copy: u64,
+
+    /// Reads that returned fewer bytes than requested, but not zero.
+    pub(crate) read_short: u64,
+
+    /// Reads that returned zero bytes, i.e. hit the end of the file.
+    pub(crate) read_eof: u64,
+
+    /// Writes that wrote fewer bytes than requested.
+    pub(crate) write_short: u64,
+
+    /// Protocol of sockets, e.g. `tcp`, `udp` or `unix`.
+    pub(crate) protocol: Option<String>,
+
+    /// How many times the file was opened, see [`aggregate`].
+    pub(crate) opens: u64,
+
+    /// Failed syscalls, counted by syscall and error, e.g. `openat` and
+    /// `ENOENT`.
+    pub(crate) errors: BTreeMap<(String, String), u64>,
+}
+
+/// Time spent in syscalls, available for traces created with `strace -T`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Timing {
+    pub ops: u64,
+    pub total: Duration,
+    pub max: Duration,
+}
+
+impl Timing {
+    fn update(&mut self, duration: Duration) {
+        self.ops += 1;
+        self.total += duration;
+        self.max = self.max.max(duration);
+    }
+
+    fn merge(&mut self, other: Self) {
+        self.ops += other.ops;
+        self.total += other.total;
+        self.max = self.max.max(other.max);
+    }
+
+    #[must_use]
+    pub fn mean(&self) -> Duration {
+        let nanos = self.total.as_nanos() / u128::from(self.ops.max(1));
+        Duration::from_nanos(u64::try_from(nanos).unwrap_or(u64::MAX))
+    }
+}
+
+/// Distribution of the sizes of I/O operations, i.e. of the requested buffer
+/// sizes or of the bytes actually returned.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(Serialize))]
+pub struct OpStats {
+    #[cfg_attr(feature = "json", serde(skip))]
+    pub ops: u64,
+
+    /// The most frequent size.
+    pub mode: u64,
+    pub mean: u64,
+    pub median: u64,
+
+    /// The 99th percentile.
+    pub p99: u64,
+}
+
+impl OpStats {
+    /// Returns the statistics of the sizes and their frequencies in `freq`,
+    /// `None` if there were no operations.
+    #[must_use]
+    pub fn new(freq: &HashMap<u64, u64>) -> Option<Self> {
+        let mut sizes: Vec<(u64, u64)> =
+            freq.iter().map(|(size, n)| (*size, *n)).collect();
+
+        sizes.sort_unstable();
+
+        let ops: u64 = sizes.iter().map(|(_, n)| n).sum();
+
+        if ops == 0 {
+            return None;
+        }
+
+        // ties go to the smaller size
+        let (mode, _) =
+            sizes.iter().copied().max_by(|(s1, n1), (s2, n2)| {
+                n1.cmp(n2).then_with(|| s2.cmp(s1))
+            })?;
+
+        let total: u128 = sizes
+            .iter()
+            .map(|(size, n)| u128::from(*size) * u128::from(*n))
+            .sum();
+
+        let mean = u64::try_from(total / u128::from(ops)).unwrap_or(u64::MAX);
+
+        // nearest-rank method
+        let percentile = |p: u64| {
+            let rank = (ops * p).div_ceil(100);
+            let mut seen = 0;
+
+            sizes
+                .iter()
+                .find(|(_, n)| {
+                    seen += n;
+                    seen >= rank
+                })
+                .map_or(0, |(size, _)| *size)
+        };
+
+        Some(Self {
+            ops,
+            mode,
+            mean,
+            median: percentile(50),
+            p99: percentile(99),
+        })
+    }
 }
 
 impl Summary {
+    #[must_use]
     pub fn new(file: &str) -> Self {
         Self {
+            pid: None,
+            program: None,
             file: String::from(file),
             read_freq: HashMap::new(),
             write_freq: HashMap::new(),
+            read_returned: HashMap::new(),
+            write_returned: HashMap::new(),
             read_bytes: 0,
             write_bytes: 0,
+            read_time: None,
+            write_time: None,
+            read_zero_copy: 0,
+            write_zero_copy: 0,
+            read_short: 0,
+            read_eof: 0,
+            write_short: 0,
+            protocol: None,
+            opens: 0,
+            errors: BTreeMap::new(),
         }
     }
 
+    /// PID of the process that did the I/O, if known.
+    #[must_use]
+    pub const fn pid(&self) -> Option<u32> {
+        self.pid
+    }
+
+    /// Path of the program that the process executed, if known.
+    #[must_use]
+    pub fn program(&self) -> Option<&str> {
+        self.program.as_deref()
+    }
+
+    #[must_use]
+    pub fn file(&self) -> &str {
+        &self.file
+    }
+
+    /// Protocol of sockets, e.g. `tcp`, `udp` or `unix`.
+    #[must_use]
+    pub fn protocol(&self) -> Option<&str> {
+        self.protocol.as_deref()
+    }
+
+    /// How many times the file was opened, see [`aggregate`].
+    #[must_use]
+    pub const fn opens(&self) -> u64 {
+        self.opens
+    }
+
+    #[must_use]
+    pub const fn read_bytes(&self) -> u64 {
+        self.read_bytes
+    }
+
+    #[must_use]
+    pub const fn write_bytes(&self) -> u64 {
+        self.write_bytes
+    }
+
+    /// Frequencies of the requested buffer sizes of reads.
+    #[must_use]
+    pub const fn read_freq(&self) -> &HashMap<u64, u64> {
+        &self.read_freq
+    }
+
+    /// Frequencies of the requested buffer sizes of writes.
+    #[must_use]
+    pub const fn write_freq(&self) -> &HashMap<u64, u64> {
+        &self.write_freq
+    }
+
+    /// Frequencies of the bytes actually returned by reads.
+    #[must_use]
+    pub const fn read_returned(&self) -> &HashMap<u64, u64> {
+        &self.read_returned
+    }
+
+    /// Frequencies of the bytes actually returned by writes.
+    #[must_use]
+    pub const fn write_returned(&self) -> &HashMap<u64, u64> {
+        &self.write_returned
+    }
+
+    /// Time spent reading, if the trace has syscall times.
+    #[must_use]
+    pub const fn read_time(&self) -> Option<Timing> {
+        self.read_time
+    }
+
+    /// Time spent writing, if the trace has syscall times.
+    #[must_use]
+    pub const fn write_time(&self) -> Option<Timing> {
+        self.write_time
+    }
+
+    /// Bytes read via zero-copy syscalls like `sendfile` or `splice`.
+    #[must_use]
+    pub const fn read_zero_copy(&self) -> u64 {
+        self.read_zero_copy
+    }
+
+    /// Bytes written via zero-copy syscalls like `sendfile` or `splice`.
+    #[must_use]
+    pub const fn write_zero_copy(&self) -> u64 {
+        self.write_zero_copy
+    }
+
+    /// Reads that returned fewer bytes than requested, but not zero.
+    #[must_use]
+    pub const fn read_short(&self) -> u64 {
+        self.read_short
+    }
+
+    /// Reads that returned zero bytes, i.e. hit the end of the file.
+    #[must_use]
+    pub const fn read_eof(&self) -> u64 {
+        self.read_eof
+    }
+
+    /// Writes that wrote fewer bytes than requested.
+    #[must_use]
+    pub const fn write_short(&self) -> u64 {
+        self.write_short
+    }
+
+    /// Failed syscalls, counted by syscall and error.
+    #[must_use]
+    pub const fn errors(&self) -> &BTreeMap<(String, String), u64> {
+        &self.errors
+    }
+
+    #[must_use]
     pub fn pipe() -> Self {
         Self::new("PIPE")
     }
 
-    pub fn socket() -> Self {
-        Self::new("SOCKET")
+    #[must_use]
+    pub fn socket(protocol: Option<String>) -> Self {
+        Self {
+            protocol,
+            ..Self::new("SOCKET")
+        }
     }
 
     pub fn reset(&mut self) {
         self.read_freq.clear();
         self.write_freq.clear();
+        self.read_returned.clear();
+        self.write_returned.clear();
         self.read_bytes = 0;
         self.write_bytes = 0;
+        self.read_time = None;
+        self.write_time = None;
+        self.read_zero_copy = 0;
+        self.write_zero_copy = 0;
+        self.read_short = 0;
+        self.read_eof = 0;
+        self.write_short = 0;
+        self.opens = 0;
+        self.errors.clear();
+    }
+
+    /// Adds the I/O of `other` to this summary. The PID and the program are
+    /// kept only if both summaries agree on them.
+    pub fn merge(&mut self, other: &Self) {
+        if self.pid != other.pid {
+            self.pid = None;
+        }
+
+        if self.program != other.program {
+            self.program = None;
+        }
+
+        merge_freq(&mut self.read_freq, &other.read_freq);
+        merge_freq(&mut self.write_freq, &other.write_freq);
+        merge_freq(&mut self.read_returned, &other.read_returned);
+        merge_freq(&mut self.write_returned, &other.write_returned);
+
+        self.read_bytes += other.read_bytes;
+        self.write_bytes += other.write_bytes;
+
+        if let Some(timing) = other.read_time {
+            self.read_time.get_or_insert_default().merge(timing);
+        }
+
+        if let Some(timing) = other.write_time {
+            self.write_time.get_or_insert_default().merge(timing);
+        }
+
+        self.read_zero_copy += other.read_zero_copy;
+        self.write_zero_copy += other.write_zero_copy;
+        self.read_short += other.read_short;
+        self.read_eof += other.read_eof;
+        self.write_short += other.write_short;
+        self.opens += other.opens;
+
+        for (error, n) in &other.errors {
+            *self.errors.entry(error.clone()).or_insert(0) += n;
+        }
+    }
+
+    pub fn update_read(
+        &mut self,
+        op_size: u64,
+        bytes: u64,
+        duration: Option<Duration>,
+    ) {
+        if bytes == 0 {
+            self.read_eof += 1;
+        } else if bytes < op_size {
+            self.read_short += 1;
+        }
+
+        self.record_read(op_size, bytes, duration);
+    }
+
+    pub fn update_write(
+        &mut self,
+        op_size: u64,
+        bytes: u64,
+        duration: Option<Duration>,
+    ) {
+        if bytes < op_size {
+            self.write_short += 1;
+        }
+
+        self.record_write(op_size, bytes, duration);
     }
 
-    pub fn update_read(&mut self, op_size: u64, bytes: u64) {
+    pub fn update_error(&mut self, syscall: &str, error: &str) {
+        let key = (String::from(syscall), String::from(error));
+        *self.errors.entry(key).or_insert(0) += 1;
+    }
+
+    /// Records a zero-copy read. The requested size of zero-copy transfers
+    /// is usually just an upper bound, so they're never counted as short.
+    pub fn update_read_zero_copy(
+        &mut self,
+        op_size: u64,
+        bytes: u64,
+        duration: Option<Duration>,
+    ) {
+        self.record_read(op_size, bytes, duration);
+        self.read_zero_copy += bytes;
+    }
+
+    /// Records a zero-copy write, see [`Self::update_read_zero_copy`].
+    pub fn update_write_zero_copy(
+        &mut self,
+        op_size: u64,
+        bytes: u64,
+        duration: Option<Duration>,
+    ) {
+        self.record_write(op_size, bytes, duration);
+        self.write_zero_copy += bytes;
+    }
+
+    fn record_read(
+        &mut self,
+        op_size: u64,
+        bytes: u64,
+        duration: Option<Duration>,
+    ) {
         let freq = self.read_freq.entry(op_size).or_insert(0);
         *freq += 1;
+        let returned = self.read_returned.entry(bytes).or_insert(0);
+        *returned += 1;
         self.read_bytes += bytes;
+
+        if let Some(duration) = duration {
+            self.read_time.get_or_insert_default().update(duration);
+        }
     }
 
-    pub fn update_write(&mut self, op_size: u64, bytes: u64) {
+    fn record_write(
+        &mut self,
+        op_size: u64,
+        bytes: u64,
+        duration: Option<Duration>,
+    ) {
         let freq = self.write_freq.entry(op_size).or_insert(0);
         *freq += 1;
+        let returned = self.write_returned.entry(bytes).or_insert(0);
+        *returned += 1;
         self.write_bytes += bytes;
+
+        if let Some(duration) = duration {
+            self.write_time.get_or_insert_default().update(duration);
+        }
+    }
+
+    #[must_use]
+    pub fn should_show(&self, config: &Config) -> bool {
+        config.verbose || config.filter.is_shown(&self.file)
+    }
+
+    /// Shows one row per direction with values separated by `separator`,
+    /// e.g. for CSV or TSV.
+    pub fn show_delimited(&self, config: &Config, separator: char) {
+        if !self.should_show(config) {
+            return;
+        }
+
+        for row in self.delimited(separator) {
+            println!("{row}");
+        }
+    }
+
+    /// Returns the rows of the delimiter-separated output, one per
+    /// direction.
+    fn delimited(&self, separator: char) -> Vec<String> {
+        let mut rows = vec![];
+        let pid = self.pid.map_or_else(String::new, |pid| pid.to_string());
+
+        for (direction, returned, bytes, short, eof) in [
+            (
+                "read",
+                &self.read_returned,
+                self.read_bytes,
+                self.read_short,
+                self.read_eof,
+            ),
+            (
+                "write",
+                &self.write_returned,
+                self.write_bytes,
+                self.write_short,
+                0,
+            ),
+        ] {
+            let Some(stats) = OpStats::new(returned) else {
+                continue;
+            };
+
+            let row = [
+                pid.clone(),
+                String::from(direction),
+                stats.ops.to_string(),
+                humanize(bytes),
+                humanize(stats.mean),
+                bytes.to_string(),
+                stats.mean.to_string(),
+                short.to_string(),
+                eof.to_string(),
+                self.opens.to_string(),
+                quote(&self.file, separator),
+            ];
+
+            rows.push(row.join(&separator.to_string()));
+        }
+
+        rows
     }
 
-    pub fn should_show(&self, config: Config) -> bool {
-        config.verbose
-            || !(self.file.starts_with("/bin/")
-                || self.file == "/dev/null"
-                || self.file.starts_with("/etc/")
-                || self.file.starts_with("/lib/")
-                || self.file.starts_with("/lib64/")
-                || self.file.starts_with("/opt/")
-                || self.file.starts_with("/proc/")
-                || self.file.starts_with("/run/")
-                || self.file.starts_with("/sbin/")
-                || self.file.starts_with("/sys/")
-                || self.file.starts_with("/tmp/")
-                || self.file.starts_with("/usr/")
-                || self.file == "STDOUT"
-                || self.file == "STDERR"
-                || self.file == "STDIN"
-                || self.file == "SOCKET"
-                || self.file == "DUP"
-                || self.file == "PIPE")
-    }
-
-    pub fn show(&self, config: Config) {
+    pub fn show(&self, config: &Config) {
         if !self.should_show(config) {
             return;
         }
 
-        if self.read_freq.is_empty() && self.write_freq.is_empty() {
+        if self.read_freq.is_empty()
+            && self.write_freq.is_empty()
+            && self.errors.is_empty()
+        {
             debug(format!("no I/O with {}", self.file), config);
             return;
         }
 
-        if !self.read_freq.is_empty() {
-            let (op_size, _) = self.read_freq.iter().max().unwrap();
-            let n_ops: u64 = self.read_freq.values().sum();
+        for line in self.lines() {
+            println!("{line}");
+        }
+
+        if config.histogram {
+            self.show_histogram(config);
+        }
+    }
 
-            println!(
-                "read {} with {} ops ({} / op) {}",
+    /// Returns the lines of the continuous output, one per direction and
+    /// one per error.
+    pub(crate) fn lines(&self) -> Vec<String> {
+        let mut lines = vec![];
+
+        if let Some(stats) = OpStats::new(&self.read_returned) {
+            lines.push(format!(
+                "read {} with {} ops ({}{}{}{}{}){} {}",
                 humanize(self.read_bytes),
-                n_ops,
-                humanize(*op_size),
+                stats.ops,
+                show_op_sizes(stats),
+                show_zero_copy(self.read_zero_copy),
+                show_count(self.read_short, "short"),
+                show_count(self.read_eof, "EOF"),
+                show_opens(self.opens),
+                self.read_time.map_or_else(String::new, show_timing),
                 self.file,
-            );
+            ));
         }
 
-        if !self.write_freq.is_empty() {
-            let (op_size, _) = self.write_freq.iter().max().unwrap();
-            let n_ops: u64 = self.write_freq.values().sum();
-
-            println!(
-                "write {} with {} ops ({} / op) {}",
+        if let Some(stats) = OpStats::new(&self.write_returned) {
+            lines.push(format!(
+                "write {} with {} ops ({}{}{}{}){} {}",
                 humanize(self.write_bytes),
-                n_ops,
-                humanize(*op_size),
+                stats.ops,
+                show_op_sizes(stats),
+                show_zero_copy(self.write_zero_copy),
+                show_count(self.write_short, "short"),
+                show_opens(self.opens),
+                self.write_time.map_or_else(String::new, show_timing),
                 self.file,
-            );
+            ));
+        }
+
+        for ((syscall, error), n) in &self.errors {
+            lines.push(format!(
+                "{syscall} failed {n} times with {error} {}",
+                self.file
+            ));
         }
+
+        lines
+    }
+
+    /// Shows the histograms of the requested buffer sizes, together with the
+    /// statistics of the requested and the returned sizes.
+    pub fn show_histogram(&self, config: &Config) {
+        if !self.should_show(config) {
+            return;
+        }
+
+        for (direction, freq, returned) in [
+            ("read", &self.read_freq, &self.read_returned),
+            ("write", &self.write_freq, &self.write_returned),
+        ] {
+            let (Some(requested_stats), Some(returned_stats)) =
+                (OpStats::new(freq), OpStats::new(returned))
+            else {
+                continue;
+            };
+
+            println!("{direction} sizes of {}", self.file);
+            println!("  requested {}", show_stats(requested_stats));
+            println!("  returned  {}", show_stats(returned_stats));
+
+            let mut sizes: Vec<_> = freq.iter().collect();
+            sizes.sort_unstable();
+
+            let max = sizes.iter().map(|(_, n)| **n).max().unwrap_or(1);
+
+            for (size, n) in sizes {
+                let width = (u128::from(*n) * HISTOGRAM_WIDTH)
+                    .div_ceil(u128::from(max));
+                let bar = "#".repeat(usize::try_from(width).unwrap_or(0));
+
+                println!("  {:>8} {bar} {n}", humanize(*size));
+            }
+
+            println!();
+        }
+    }
+}
+
+/// Width of the longest bar of histograms.
+const HISTOGRAM_WIDTH: u128 = 40;
+
+fn merge_freq(freq: &mut HashMap<u64, u64>, other: &HashMap<u64, u64>) {
+    for (size, n) in other {
+        *freq.entry(*size).or_insert(0) += n;
     }
 }
 
-fn humanize(bytes: u64) -> String {
+/// Merges the summaries of the same file, e.g. of a file that was opened and
+/// closed repeatedly or by multiple processes, keeping the order in which the
+/// files were first encountered.
+#[must_use]
+pub fn aggregate(summaries: Vec<Summary>) -> Vec<Summary> {
+    let mut aggregated: Vec<Summary> = vec![];
+    let mut index: HashMap<String, usize> = HashMap::new();
+
+    for summary in summaries {
+        if let Some(i) = index.get(&summary.file) {
+            aggregated[*i].merge(&summary);
+        } else {
+            index.insert(summary.file.clone(), aggregated.len());
+            aggregated.push(summary);
+        }
+    }
+
+    aggregated
+}
+
+/// Header of the delimiter-separated output, see [`Summary::show_delimited`].
+pub fn show_delimited_header(separator: char) {
+    println!("{}", delimited_header(separator));
+}
+
+fn delimited_header(separator: char) -> String {
+    let header = [
+        "pid",
+        "direction",
+        "ops",
+        "bytes",
+        "bytes_per_op",
+        "bytes_raw",
+        "bytes_per_op_raw",
+        "short",
+        "eof",
+        "opens",
+        "file",
+    ];
+
+    header.join(&separator.to_string())
+}
+
+/// Quotes a field of delimiter-separated output if necessary.
+fn quote(field: &str, separator: char) -> String {
+    if field.contains([separator, '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        String::from(field)
+    }
+}
+
+pub(crate) fn humanize(bytes: u64) -> String {
     ByteSize(bytes).display().iec_short().to_string()
 }
 
+fn humanize_time(duration: Duration) -> String {
+    format!("{duration:.1?}")
+}
+
+fn show_zero_copy(bytes: u64) -> String {
+    if bytes == 0 {
+        String::new()
+    } else {
+        format!(", {} zero-copy", humanize(bytes))
+    }
+}
+
+fn show_opens(opens: u64) -> String {
+    if opens > 1 {
+        format!(", {opens} opens")
+    } else {
+        String::new()
+    }
+}
+
+fn show_count(n: u64, what: &str) -> String {
+    if n == 0 {
+        String::new()
+    } else {
+        format!(", {n} {what}")
+    }
+}
+
+/// Shows the mean size of the operations, followed by the other statistics.
+fn show_op_sizes(stats: OpStats) -> String {
+    format!(
+        "{} / op, mode {}, median {}, p99 {}",
+        humanize(stats.mean),
+        humanize(stats.mode),
+        humanize(stats.median),
+        humanize(stats.p99),
+    )
+}
+
+fn show_stats(stats: OpStats) -> String {
+    format!(
+        "mode {}, mean {}, median {}, p99 {}",
+        humanize(stats.mode),
+        humanize(stats.mean),
+        humanize(stats.median),
+        humanize(stats.p99),
+    )
+}
+
+fn show_timing(timing: Timing) -> String {
+    format!(
+        " in {} ({} / op, {} max)",
+        humanize_time(timing.total),
+        humanize_time(timing.mean()),
+        humanize_time(timing.max),
+    )
+}
+
 #[cfg(feature = "table")]
-pub fn show_table(summaries: &[Summary], config: Config) {
+pub fn show_table(summaries: &[Summary], config: &Config) {
     let format = FormatBuilder::new().column_separator(' ').build();
 
+    let timed = summaries.iter().any(|summary| {
+        summary.read_time.is_some() || summary.write_time.is_some()
+    });
+
+    let zero_copy = summaries.iter().any(|summary| {
+        summary.read_zero_copy > 0 || summary.write_zero_copy > 0
+    });
+
+    let short_reads = summaries
+        .iter()
+        .any(|summary| summary.read_short > 0 || summary.read_eof > 0);
+
+    let short_writes = summaries.iter().any(|summary| summary.write_short > 0);
+
     let mut r_table = Table::new();
     let mut w_table = Table::new();
 
     r_table.set_format(format);
     w_table.set_format(format);
 
-    let mut r_titles = Row::empty();
-    r_titles.add_cell(cell!(bu->"Reads"));
-    r_titles.add_cell(cell!(bu->"Bytes"));
-    r_titles.add_cell(cell!(bu->"Bytes/Op"));
-    r_titles.add_cell(cell!(bu->"File"));
-    r_table.set_titles(r_titles);
+    let short_titles: &[&str] =
+        if short_reads { &["Short", "EOF"] } else { &[] };
+    r_table.set_titles(titles(
+        "Reads",
+        zero_copy,
+        short_titles,
+        timed,
+        config.aggregate,
+    ));
 
-    let mut w_titles = Row::empty();
-    w_titles.add_cell(cell!(bu->"Writes"));
-    w_titles.add_cell(cell!(bu->"Bytes"));
-    w_titles.add_cell(cell!(bu->"Bytes/Op"));
-    w_titles.add_cell(cell!(bu->"File"));
-    w_table.set_titles(w_titles);
+    let short_titles: &[&str] = if short_writes { &["Short"] } else { &[] };
+    w_table.set_titles(titles(
+        "Writes",
+        zero_copy,
+        short_titles,
+        timed,
+        config.aggregate,
+    ));
 
     for summary in summaries {
         if !summary.should_show(config) {
             continue;
         }
 
-        if !summary.read_freq.is_empty() {
+        if let Some(stats) = OpStats::new(&summary.read_returned) {
             let mut row = Row::empty();
 
-            let (op_size, _) = summary.read_freq.iter().max().unwrap();
-            let n_ops: u64 = summary.read_freq.values().sum();
-
-            row.add_cell(cell!(n_ops));
+            row.add_cell(cell!(stats.ops));
             row.add_cell(cell!(humanize(summary.read_bytes)));
-            row.add_cell(cell!(humanize(*op_size)));
+            add_op_size_cells(&mut row, stats);
+            if zero_copy {
+                row.add_cell(cell!(humanize(summary.read_zero_copy)));
+            }
+            if short_reads {
+                row.add_cell(cell!(summary.read_short));
+                row.add_cell(cell!(summary.read_eof));
+            }
+            if timed {
+                add_timing_cells(&mut row, summary.read_time);
+            }
+            if config.aggregate {
+                row.add_cell(cell!(summary.opens));
+            }
             row.add_cell(cell!(summary.file));
 
             r_table.add_row(row);
         }
 
-        if !summary.write_freq.is_empty() {
+        if let Some(stats) = OpStats::new(&summary.write_returned) {
             let mut row = Row::empty();
 
-            let (op_size, _) = summary.write_freq.iter().max().unwrap();
-            let n_ops: u64 = summary.write_freq.values().sum();
-
-            row.add_cell(cell!(n_ops));
+            row.add_cell(cell!(stats.ops));
             row.add_cell(cell!(humanize(summary.write_bytes)));
-            row.add_cell(cell!(humanize(*op_size)));
+            add_op_size_cells(&mut row, stats);
+            if zero_copy {
+                row.add_cell(cell!(humanize(summary.write_zero_copy)));
+            }
+            if short_writes {
+                row.add_cell(cell!(summary.write_short));
+            }
+            if timed {
+                add_timing_cells(&mut row, summary.write_time);
+            }
+            if config.aggregate {
+                row.add_cell(cell!(summary.opens));
+            }
             row.add_cell(cell!(summary.file));
 
             w_table.add_row(row);
@@ -211,4 +886,383 @@ pub fn show_table(summaries: &[Summary], config: Config) {
         w_table.printstd();
         println!();
     }
+
+    show_error_table(summaries, config);
+}
+
+#[cfg(feature = "table")]
+fn show_error_table(summaries: &[Summary], config: &Config) {
+    let format = FormatBuilder::new().column_separator(' ').build();
+
+    let mut table = Table::new();
+    table.set_format(format);
+
+    let mut titles = Row::empty();
+    titles.add_cell(cell!(bu->"Errors"));
+    titles.add_cell(cell!(bu->"Syscall"));
+    titles.add_cell(cell!(bu->"Error"));
+    titles.add_cell(cell!(bu->"File"));
+    table.set_titles(titles);
+
+    for summary in summaries {
+        if !summary.should_show(config) {
+            continue;
+        }
+
+        for ((syscall, error), n) in &summary.errors {
+            let mut row = Row::empty();
+            row.add_cell(cell!(n));
+            row.add_cell(cell!(syscall));
+            row.add_cell(cell!(error));
+            row.add_cell(cell!(summary.file));
+            table.add_row(row);
+        }
+    }
+
+    if !table.is_empty() {
+        table.printstd();
+        println!();
+    }
+}
+
+#[cfg(feature = "table")]
+fn titles(
+    ops: &str,
+    zero_copy: bool,
+    short: &[&str],
+    timed: bool,
+    opens: bool,
+) -> Row {
+    let mut titles = Row::empty();
+    titles.add_cell(cell!(bu->ops));
+    titles.add_cell(cell!(bu->"Bytes"));
+    titles.add_cell(cell!(bu->"Bytes/Op"));
+    titles.add_cell(cell!(bu->"Mode"));
+    titles.add_cell(cell!(bu->"Median"));
+    titles.add_cell(cell!(bu->"P99"));
+    if zero_copy {
+        titles.add_cell(cell!(bu->"Zero-Copy"));
+    }
+    for title in short {
+        titles.add_cell(cell!(bu->title));
+    }
+    if timed {
+        add_timing_titles(&mut titles);
+    }
+    if opens {
+        titles.add_cell(cell!(bu->"Opens"));
+    }
+    titles.add_cell(cell!(bu->"File"));
+    titles
+}
+
+#[cfg(feature = "table")]
+fn add_op_size_cells(row: &mut Row, stats: OpStats) {
+    row.add_cell(cell!(humanize(stats.mean)));
+    row.add_cell(cell!(humanize(stats.mode)));
+    row.add_cell(cell!(humanize(stats.median)));
+    row.add_cell(cell!(humanize(stats.p99)));
+}
+
+#[cfg(feature = "table")]
+fn add_timing_titles(titles: &mut Row) {
+    titles.add_cell(cell!(bu->"Time"));
+    titles.add_cell(cell!(bu->"Time/Op"));
+    titles.add_cell(cell!(bu->"Max"));
+}
+
+#[cfg(feature = "table")]
+fn add_timing_cells(row: &mut Row, timing: Option<Timing>) {
+    if let Some(timing) = timing {
+        row.add_cell(cell!(humanize_time(timing.total)));
+        row.add_cell(cell!(humanize_time(timing.mean())));
+        row.add_cell(cell!(humanize_time(timing.max)));
+    } else {
+        row.add_cell(cell!("-"));
+        row.add_cell(cell!("-"));
+        row.add_cell(cell!("-"));
+    }
+}
+
+#[cfg(feature = "json")]
+#[derive(Serialize)]
+struct JsonSummary<'a> {
+    pid: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    program: Option<&'a str>,
+    file: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    protocol: Option<&'a str>,
+    opens: u64,
+    read: JsonIo,
+    write: JsonIo,
+    errors: Vec<JsonError<'a>>,
+}
+
+#[cfg(feature = "json")]
+#[derive(Serialize)]
+struct JsonError<'a> {
+    syscall: &'a str,
+    error: &'a str,
+    count: u64,
+}
+
+#[cfg(feature = "json")]
+#[derive(Serialize)]
+struct JsonIo {
+    ops: u64,
+    bytes: u64,
+    zero_copy: u64,
+    short: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    eof: Option<u64>,
+    freq: BTreeMap<u64, u64>,
+    returned_freq: BTreeMap<u64, u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    requested: Option<OpStats>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    returned: Option<OpStats>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    time: Option<JsonTiming>,
+}
+
+/// Time spent in syscalls, in seconds.
+#[cfg(feature = "json")]
+#[derive(Serialize)]
+struct JsonTiming {
+    ops: u64,
+    total: f64,
+    mean: f64,
+    max: f64,
+}
+
+#[cfg(feature = "json")]
+impl JsonIo {
+    fn new(
+        (freq, returned): (&HashMap<u64, u64>, &HashMap<u64, u64>),
+        bytes: u64,
+        zero_copy: u64,
+        (short, eof): (u64, Option<u64>),
+        time: Option<Timing>,
+    ) -> Self {
+        Self {
+            ops: freq.values().sum(),
+            bytes,
+            zero_copy,
+            short,
+            eof,
+            freq: freq.iter().map(|(size, n)| (*size, *n)).collect(),
+            returned_freq: returned
+                .iter()
+                .map(|(size, n)| (*size, *n))
+                .collect(),
+            requested: OpStats::new(freq),
+            returned: OpStats::new(returned),
+            time: time.map(|time| JsonTiming {
+                ops: time.ops,
+                total: time.total.as_secs_f64(),
+                mean: time.mean().as_secs_f64(),
+                max: time.max.as_secs_f64(),
+            }),
+        }
+    }
+}
+
+/// Prints the summaries as a JSON array.
+///
+/// # Errors
+///
+/// Returns an error if the JSON can't be written to stdout.
+#[cfg(feature = "json")]
+pub fn show_json(summaries: &[Summary], config: &Config) -> Result<()> {
+    let summaries = json_summaries(summaries, config);
+
+    serde_json::to_writer_pretty(std::io::stdout().lock(), &summaries)?;
+    println!();
+
+    Ok(())
+}
+
+#[cfg(feature = "json")]
+fn json_summaries<'a>(
+    summaries: &'a [Summary],
+    config: &Config,
+) -> Vec<JsonSummary<'a>> {
+    summaries
+        .iter()
+        .filter(|summary| summary.should_show(config))
+        .map(|summary| JsonSummary {
+            pid: summary.pid,
+            program: summary.program.as_deref(),
+            file: &summary.file,
+            protocol: summary.protocol.as_deref(),
+            opens: summary.opens,
+            read: JsonIo::new(
+                (&summary.read_freq, &summary.read_returned),
+                summary.read_bytes,
+                summary.read_zero_copy,
+                (summary.read_short, Some(summary.read_eof)),
+                summary.read_time,
+            ),
+            write: JsonIo::new(
+                (&summary.write_freq, &summary.write_returned),
+                summary.write_bytes,
+                summary.write_zero_copy,
+                (summary.write_short, None),
+                summary.write_time,
+            ),
+            errors: summary
+                .errors
+                .iter()
+                .map(|((syscall, error), count)| JsonError {
+                    syscall,
+                    error,
+                    count: *count,
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+// ----------------------------------------------------------------------------
+// tests
+// ----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn op_stats() {
+        let freq = HashMap::from([(4096, 999_999), (1_048_576, 1)]);
+
+        assert_eq!(
+            OpStats::new(&freq),
+            Some(OpStats {
+                ops: 1_000_000,
+                mode: 4096,
+                mean: 4097,
+                median: 4096,
+                p99: 4096,
+            })
+        );
+
+        let freq = HashMap::from([(10, 50), (20, 49), (30, 1)]);
+
+        assert_eq!(
+            OpStats::new(&freq),
+            Some(OpStats {
+                ops: 100,
+                mode: 10,
+                mean: 15,
+                median: 10,
+                p99: 20,
+            })
+        );
+
+        assert_eq!(OpStats::new(&HashMap::new()), None);
+    }
+
+    #[test]
+    fn continuous_lines() {
+        let summary = Summary {
+            read_freq: HashMap::from([(4096, 2), (1_048_576, 1)]),
+            read_returned: HashMap::from([(4096, 2), (1_048_576, 1)]),
+            read_bytes: 1_056_768,
+            ..Summary::new("/data/input")
+        };
+
+        assert_eq!(
+            summary.lines(),
+            [
+                "read 1.0M with 3 ops (344.0K / op, mode 4.0K, median 4.0K, \
+                 p99 1.0M) /data/input"
+            ]
+        );
+    }
+
+    #[test]
+    fn delimited() {
+        assert_eq!(
+            delimited_header(','),
+            "pid,direction,ops,bytes,bytes_per_op,bytes_raw,\
+             bytes_per_op_raw,short,eof,opens,file"
+        );
+        assert_eq!(delimited_header('\t').split('\t').count(), 11);
+
+        let summary = Summary {
+            pid: Some(42),
+            read_freq: HashMap::from([(1_048_576, 2)]),
+            read_returned: HashMap::from([(1_048_576, 2)]),
+            read_bytes: 2_097_152,
+            read_eof: 1,
+            opens: 1,
+            ..Summary::new("/data/input")
+        };
+
+        assert_eq!(
+            summary.delimited(','),
+            ["42,read,2,2.0M,1.0M,2097152,1048576,0,1,1,/data/input"]
+        );
+
+        let summary = Summary {
+            write_freq: HashMap::from([(100, 1)]),
+            write_returned: HashMap::from([(100, 1)]),
+            write_bytes: 100,
+            ..Summary::new("/data/a,b")
+        };
+
+        assert_eq!(
+            summary.delimited(','),
+            [",write,1,100B,100B,100,100,0,0,0,\"/data/a,b\""]
+        );
+        assert_eq!(
+            summary.delimited('\t'),
+            ["\twrite\t1\t100B\t100B\t100\t100\t0\t0\t0\t/data/a,b"]
+        );
+
+        assert_eq!(quote("/data/a\tb", '\t'), "\"/data/a\tb\"");
+        assert_eq!(quote("/data/a\tb", ','), "/data/a\tb");
+        assert_eq!(quote("/data/\"a\"", ','), "\"/data/\"\"a\"\"\"");
+        assert_eq!(quote("/data/a\nb", '\t'), "\"/data/a\nb\"");
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json_schema() {
+        let summary = Summary {
+            pid: Some(42),
+            read_freq: HashMap::from([(4096, 3), (1024, 1)]),
+            read_returned: HashMap::from([(4096, 3), (512, 1)]),
+            read_bytes: 12_800,
+            write_freq: HashMap::from([(100, 2)]),
+            write_returned: HashMap::from([(100, 2)]),
+            write_bytes: 200,
+            opens: 1,
+            ..Summary::new("/data/input")
+        };
+
+        let summaries = [summary];
+        let json = serde_json::to_value(json_summaries(
+            &summaries,
+            &Config::default(),
+        ))
+        .unwrap();
+
+        let json = &json[0];
+        assert_eq!(json["pid"], 42);
+        assert_eq!(json["file"], "/data/input");
+        assert_eq!(json["opens"], 1);
+
+        assert_eq!(json["read"]["ops"], 4);
+        assert_eq!(json["read"]["bytes"], 12_800);
+        assert_eq!(json["read"]["freq"]["1024"], 1);
+        assert_eq!(json["read"]["freq"]["4096"], 3);
+        assert_eq!(json["read"]["returned_freq"]["512"], 1);
+
+        assert_eq!(json["write"]["ops"], 2);
+        assert_eq!(json["write"]["bytes"], 200);
+        assert_eq!(json["write"]["freq"]["100"], 2);
+        assert!(json["write"].get("eof").is_none());
+    }
 }
@@ -0,0 +1,819 @@
+/* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * *
+ *                                                                           *
+ *  Copyright  (C)  2015-2024  Christian Krause                              *
+ *                                                                           *
+ *  Christian Krause  <christian.krause@mailbox.org>                         *
+ *                                                                           *
+ * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * *
+ *                                                                           *
+ *  This file is part of strace-analyzer.                                    *
+ *                                                                           *
+ *  strace-analyzer is free software: you can redistribute it and/or modify  *
+ *  it under the terms of the GNU General Public License as published by     *
+ *  the Free Software Foundation, either version 3 of the license, or any    *
+ *  later version.                                                           *
+ *                                                                           *
+ *  strace-analyzer is distributed in the hope that it will be useful, but   *
+ *  WITHOUT ANY WARRANTY; without even the implied warranty of               *
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU         *
+ *  General Public License for more details.                                 *
+ *                                                                           *
+ *  You should have received a copy of the GNU General Public License along  *
+ *  with strace-analyzer. If not, see <http://www.gnu.org/licenses/>.        *
+ *                                                                           *
+ * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * */
+
+//! Typed syscalls, i.e. the syscalls that are relevant to the analysis with
+//! their arguments and return values parsed.
+
+use crate::parser::{Call, Return, elements, field, number, unquote};
+
+/// A syscall that is relevant to the analysis.
+#[derive(Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Syscall<'a> {
+    /// `accept` or `accept4` of the connection `fd` on the socket `listen`.
+    Accept {
+        listen: u32,
+        address: Option<&'a str>,
+        fd: u32,
+        cloexec: bool,
+    },
+
+    /// `bind` of the socket `fd` to a local address.
+    Bind { fd: u32, address: &'a str },
+
+    /// `clone`, `clone3`, `fork` or `vfork` of the process, creating the
+    /// process `child`, which shares the file descriptor table with
+    /// `CLONE_FILES`, e.g. a thread.
+    Clone { child: u32, shared_files: bool },
+
+    /// `close` of `fd`, with the error if it failed.
+    Close { fd: u32, error: Option<&'a str> },
+
+    /// `close_range` of the file descriptors from `first` to `last`, which
+    /// are only marked close-on-exec with `CLOSE_RANGE_CLOEXEC`. With
+    /// `CLOSE_RANGE_UNSHARE`, a shared file descriptor table is unshared
+    /// first.
+    CloseRange {
+        first: u32,
+        last: u32,
+        cloexec: bool,
+        unshare: bool,
+    },
+
+    /// `connect` of the socket `fd` to a remote address, including
+    /// non-blocking sockets that connect asynchronously.
+    Connect { fd: u32, address: &'a str },
+
+    /// `dup`, `dup2`, `dup3` or `fcntl` with `F_DUPFD` or `F_DUPFD_CLOEXEC`.
+    Dup {
+        oldfd: u32,
+        newfd: u32,
+        cloexec: bool,
+    },
+
+    /// `execve` or `execveat` of `program`, relative to `dirfd` unless it is
+    /// the current working directory, with the arguments `args`, including
+    /// the program name. Truncated arguments end with `...`, e.g. all of
+    /// them with `strace -s 0`.
+    Exec {
+        dirfd: Option<u32>,
+        program: String,
+        args: Vec<String>,
+    },
+
+    /// Failed `execve` or `execveat` of `program`, e.g. a shell looking up
+    /// a command in the directories of its `PATH`.
+    ExecFailed {
+        dirfd: Option<u32>,
+        program: String,
+        error: &'a str,
+    },
+
+    /// `getpeername` of the socket `fd`.
+    GetPeerName { fd: u32, address: &'a str },
+
+    /// `open`, `openat`, `openat2` or `creat` of `path`, relative to `dirfd`
+    /// unless it is the current working directory.
+    Open {
+        dirfd: Option<u32>,
+        path: String,
+        fd: u32,
+        cloexec: bool,
+    },
+
+    /// Failed `open`, `openat`, `openat2` or `creat` of `path`.
+    OpenFailed {
+        dirfd: Option<u32>,
+        path: String,
+        error: &'a str,
+    },
+
+    /// `pipe` or `pipe2` with its read and write end.
+    Pipe {
+        read: u32,
+        write: u32,
+        cloexec: bool,
+    },
+
+    /// `read`, `pread64`, `readv`, `preadv` or `preadv2`.
+    Read { fd: u32, size: u64, bytes: u64 },
+
+    /// Failed read or receive of `fd`.
+    ReadFailed { fd: u32, error: &'a str },
+
+    /// `recv`, `recvfrom` or `recvmsg`, with the peer address if given.
+    Recv {
+        fd: u32,
+        size: u64,
+        bytes: u64,
+        address: Option<&'a str>,
+    },
+
+    /// `send`, `sendto` or `sendmsg`, with the peer address if given.
+    Send {
+        fd: u32,
+        size: u64,
+        bytes: u64,
+        address: Option<&'a str>,
+    },
+
+    /// `fcntl` with `F_SETFD` or `ioctl` with `FIOCLEX` or `FIONCLEX`,
+    /// setting or clearing the close-on-exec flag of `fd`.
+    SetCloexec { fd: u32, cloexec: bool },
+
+    /// `socket` creating `fd`.
+    Socket {
+        domain: &'a str,
+        kind: &'a str,
+        fd: u32,
+        cloexec: bool,
+    },
+
+    /// `socketpair` creating the connected sockets `first` and `second`.
+    SocketPair {
+        domain: &'a str,
+        kind: &'a str,
+        first: u32,
+        second: u32,
+        cloexec: bool,
+    },
+
+    /// Zero-copy transfer from `from` to `to`, i.e. `copy_file_range`,
+    /// `sendfile`, `splice` or `tee`.
+    Transfer {
+        from: u32,
+        to: u32,
+        size: u64,
+        bytes: u64,
+    },
+
+    /// `write`, `pwrite64`, `writev`, `pwritev` or `pwritev2`.
+    Write { fd: u32, size: u64, bytes: u64 },
+
+    /// Failed write or send of `fd`.
+    WriteFailed { fd: u32, error: &'a str },
+}
+
+impl<'a> Syscall<'a> {
+    /// Returns the typed syscall, `None` if the syscall isn't relevant to
+    /// the analysis.
+    #[must_use]
+    // ALLOW: a flat table of all syscalls reads better than splitting it up
+    #[allow(clippy::too_many_lines)]
+    pub fn parse(call: &Call<'a>) -> Option<Self> {
+        let failed = match call.result {
+            Return::Error(error) => Self::parse_failed(call, error),
+            _ => None,
+        };
+
+        if failed.is_some() {
+            return failed;
+        }
+
+        let arg = |index| call.arg(index);
+        let value = call.result.value();
+
+        let syscall = match call.name {
+            "accept" | "accept4" => Self::Accept {
+                listen: fd(arg(0)?)?,
+                address: arg(1).filter(|address| *address != "NULL"),
+                fd: u32::try_from(value?).ok()?,
+                cloexec: arg(3)
+                    .is_some_and(|flags| has_flag(flags, "SOCK_CLOEXEC")),
+            },
+
+            "bind" if value? == 0 => Self::Bind {
+                fd: fd(arg(0)?)?,
+                address: arg(1)?,
+            },
+
+            "clone" => Self::Clone {
+                child: u32::try_from(value?).ok()?,
+                shared_files: call
+                    .args
+                    .iter()
+                    .find_map(|arg| arg.strip_prefix("flags="))
+                    .is_some_and(clone_files),
+            },
+
+            "clone3" => Self::Clone {
+                child: u32::try_from(value?).ok()?,
+                shared_files: field(arg(0)?, "flags").is_some_and(clone_files),
+            },
+
+            "fork" | "vfork" => Self::Clone {
+                child: u32::try_from(value?).ok()?,
+                shared_files: false,
+            },
+
+            "close" => Self::Close {
+                fd: fd(arg(0)?)?,
+                error: match call.result {
+                    Return::Error(error) => Some(error),
+                    _ => None,
+                },
+            },
+
+            "connect"
+                if value == Some(0)
+                    || call.result == Return::Error("EINPROGRESS") =>
+            {
+                Self::Connect {
+                    fd: fd(arg(0)?)?,
+                    address: arg(1)?,
+                }
+            }
+
+            "creat" | "open" => Self::Open {
+                dirfd: None,
+                path: unquote(arg(0)?)?,
+                fd: u32::try_from(value?).ok()?,
+                cloexec: call.name == "open" && has_flag(arg(1)?, "O_CLOEXEC"),
+            },
+
+            "close_range" if value? == 0 => {
+                let flags = arg(2)?;
+
+                Self::CloseRange {
+                    first: fd(arg(0)?)?,
+                    last: match arg(1)? {
+                        "~0" | "~0U" | "UINT_MAX" => u32::MAX,
+                        last => fd(last)?,
+                    },
+                    cloexec: has_flag(flags, "CLOSE_RANGE_CLOEXEC"),
+                    unshare: has_flag(flags, "CLOSE_RANGE_UNSHARE"),
+                }
+            }
+
+            "dup" | "dup2" | "dup3" => Self::Dup {
+                oldfd: fd(arg(0)?)?,
+                newfd: u32::try_from(value?).ok()?,
+                cloexec: arg(2)
+                    .is_some_and(|flags| has_flag(flags, "O_CLOEXEC")),
+            },
+
+            "fcntl" if matches!(arg(1)?, "F_DUPFD" | "F_DUPFD_CLOEXEC") => {
+                Self::Dup {
+                    oldfd: fd(arg(0)?)?,
+                    newfd: u32::try_from(value?).ok()?,
+                    cloexec: arg(1)? == "F_DUPFD_CLOEXEC",
+                }
+            }
+
+            "fcntl" if arg(1)? == "F_SETFD" && value? == 0 => {
+                Self::SetCloexec {
+                    fd: fd(arg(0)?)?,
+                    cloexec: has_flag(arg(2)?, "FD_CLOEXEC"),
+                }
+            }
+
+            "ioctl"
+                if matches!(arg(1)?, "FIOCLEX" | "FIONCLEX")
+                    && value? == 0 =>
+            {
+                Self::SetCloexec {
+                    fd: fd(arg(0)?)?,
+                    cloexec: arg(1)? == "FIOCLEX",
+                }
+            }
+
+            "execve" if value? == 0 => Self::Exec {
+                dirfd: None,
+                program: unquote(arg(0)?)?,
+                args: argv(arg(1)?),
+            },
+
+            "execveat" if value? == 0 => Self::Exec {
+                dirfd: match arg(0)? {
+                    "AT_FDCWD" => None,
+                    dirfd => Some(fd(dirfd)?),
+                },
+                program: unquote(arg(1)?)?,
+                args: argv(arg(2)?),
+            },
+
+            "getpeername" if value? == 0 => Self::GetPeerName {
+                fd: fd(arg(0)?)?,
+                address: arg(1)?,
+            },
+
+            "openat" | "openat2" => Self::Open {
+                dirfd: match arg(0)? {
+                    "AT_FDCWD" => None,
+                    dirfd => Some(fd(dirfd)?),
+                },
+                path: unquote(arg(1)?)?,
+                fd: u32::try_from(value?).ok()?,
+                cloexec: field(arg(2)?, "flags")
+                    .or_else(|| arg(2))
+                    .is_some_and(|flags| has_flag(flags, "O_CLOEXEC")),
+            },
+
+            "pipe" | "pipe2" if value? == 0 => {
+                let ends = elements(arg(0)?)?;
+
+                Self::Pipe {
+                    read: fd(ends.first()?)?,
+                    write: fd(ends.get(1)?)?,
+                    cloexec: arg(1)
+                        .is_some_and(|flags| has_flag(flags, "O_CLOEXEC")),
+                }
+            }
+
+            "read" | "pread" | "pread64" => Self::Read {
+                fd: fd(arg(0)?)?,
+                size: number(arg(2)?)?,
+                bytes: value?,
+            },
+
+            "readv" | "preadv" | "preadv2" => Self::Read {
+                fd: fd(arg(0)?)?,
+                size: iov_len(arg(1)?),
+                bytes: value?,
+            },
+
+            "recv" | "recvfrom" => Self::Recv {
+                fd: fd(arg(0)?)?,
+                size: number(arg(2)?)?,
+                bytes: value?,
+                address: arg(4).filter(|address| *address != "NULL"),
+            },
+
+            "recvmsg" => Self::Recv {
+                fd: fd(arg(0)?)?,
+                size: field(arg(1)?, "msg_iov").map_or(0, iov_len),
+                bytes: value?,
+                address: msg_name(arg(1)?),
+            },
+
+            "send" | "sendto" => Self::Send {
+                fd: fd(arg(0)?)?,
+                size: number(arg(2)?)?,
+                bytes: value?,
+                address: arg(4).filter(|address| *address != "NULL"),
+            },
+
+            "sendmsg" => Self::Send {
+                fd: fd(arg(0)?)?,
+                size: field(arg(1)?, "msg_iov").map_or(0, iov_len),
+                bytes: value?,
+                address: msg_name(arg(1)?),
+            },
+
+            "sendfile" | "sendfile64" => Self::Transfer {
+                from: fd(arg(1)?)?,
+                to: fd(arg(0)?)?,
+                size: number(arg(3)?)?,
+                bytes: value?,
+            },
+
+            "copy_file_range" | "splice" => Self::Transfer {
+                from: fd(arg(0)?)?,
+                to: fd(arg(2)?)?,
+                size: number(arg(4)?)?,
+                bytes: value?,
+            },
+
+            "socket" => Self::Socket {
+                domain: arg(0)?,
+                kind: arg(1)?.split('|').next()?,
+                fd: u32::try_from(value?).ok()?,
+                cloexec: has_flag(arg(1)?, "SOCK_CLOEXEC"),
+            },
+
+            "socketpair" if value? == 0 => {
+                let sockets = elements(arg(3)?)?;
+
+                Self::SocketPair {
+                    domain: arg(0)?,
+                    kind: arg(1)?.split('|').next()?,
+                    first: fd(sockets.first()?)?,
+                    second: fd(sockets.get(1)?)?,
+                    cloexec: has_flag(arg(1)?, "SOCK_CLOEXEC"),
+                }
+            }
+
+            "tee" => Self::Transfer {
+                from: fd(arg(0)?)?,
+                to: fd(arg(1)?)?,
+                size: number(arg(2)?)?,
+                bytes: value?,
+            },
+
+            "write" | "pwrite" | "pwrite64" => Self::Write {
+                fd: fd(arg(0)?)?,
+                size: number(arg(2)?)?,
+                bytes: value?,
+            },
+
+            "writev" | "pwritev" | "pwritev2" => Self::Write {
+                fd: fd(arg(0)?)?,
+                size: iov_len(arg(1)?),
+                bytes: value?,
+            },
+
+            _ => return None,
+        };
+
+        Some(syscall)
+    }
+
+    /// Returns the typed failed syscall, `None` if the failure isn't relevant
+    /// to the analysis or is handled like a successful syscall, e.g. `close`.
+    fn parse_failed(call: &Call<'a>, error: &'a str) -> Option<Self> {
+        let arg = |index| call.arg(index);
+
+        let syscall = match call.name {
+            "creat" | "open" => Self::OpenFailed {
+                dirfd: None,
+                path: unquote(arg(0)?)?,
+                error,
+            },
+
+            "openat" | "openat2" => Self::OpenFailed {
+                dirfd: match arg(0)? {
+                    "AT_FDCWD" => None,
+                    dirfd => Some(fd(dirfd)?),
+                },
+                path: unquote(arg(1)?)?,
+                error,
+            },
+
+            "execve" => Self::ExecFailed {
+                dirfd: None,
+                program: unquote(arg(0)?)?,
+                error,
+            },
+
+            "execveat" => Self::ExecFailed {
+                dirfd: match arg(0)? {
+                    "AT_FDCWD" => None,
+                    dirfd => Some(fd(dirfd)?),
+                },
+                program: unquote(arg(1)?)?,
+                error,
+            },
+
+            "read" | "pread" | "pread64" | "readv" | "preadv" | "preadv2"
+            | "recv" | "recvfrom" | "recvmsg" => Self::ReadFailed {
+                fd: fd(arg(0)?)?,
+                error,
+            },
+
+            "write" | "pwrite" | "pwrite64" | "writev" | "pwritev"
+            | "pwritev2" | "send" | "sendto" | "sendmsg" => {
+                Self::WriteFailed {
+                    fd: fd(arg(0)?)?,
+                    error,
+                }
+            }
+
+            _ => return None,
+        };
+
+        Some(syscall)
+    }
+}
+
+/// Parses a file descriptor argument.
+fn fd(arg: &str) -> Option<u32> {
+    arg.parse().ok()
+}
+
+/// Returns the arguments of an `execve`, marking truncated ones with `...`.
+fn argv(args: &str) -> Vec<String> {
+    elements(args)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|arg| {
+            let mut unquoted = unquote(arg).unwrap_or_default();
+
+            if arg.ends_with("...") {
+                unquoted.push_str("...");
+            }
+
+            unquoted
+        })
+        .collect()
+}
+
+/// Returns whether `CLONE_FILES` is among the flags of a `clone`.
+fn clone_files(flags: &str) -> bool {
+    has_flag(flags, "CLONE_FILES")
+}
+
+/// Returns whether `flag` is among the `|`-separated `flags`.
+fn has_flag(flags: &str, flag: &str) -> bool {
+    flags.split('|').any(|f| f == flag)
+}
+
+/// Sums up the buffer sizes of an `iovec` array of vectored I/O syscalls.
+fn iov_len(iov: &str) -> u64 {
+    elements(iov)
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|iovec| number(field(iovec, "iov_len")?))
+        .sum()
+}
+
+/// Returns the peer address of a `msghdr`, if given.
+fn msg_name(msg: &str) -> Option<&str> {
+    field(msg, "msg_name").filter(|address| *address != "NULL")
+}
+
+// ----------------------------------------------------------------------------
+// tests
+// ----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(call: &str) -> Option<Syscall<'_>> {
+        Syscall::parse(&Call::parse(call)?)
+    }
+
+    #[test]
+    fn parse_open_syscalls() {
+        assert_eq!(
+            parse(r#"openat(AT_FDCWD, "a, \"b\")", O_RDONLY) = 3"#),
+            Some(Syscall::Open {
+                dirfd: None,
+                path: String::from(r#"a, "b")"#),
+                fd: 3,
+                cloexec: false,
+            })
+        );
+
+        assert_eq!(
+            parse(r#"openat(4, "foo", O_RDONLY) = -1 ENOENT (No such file)"#),
+            Some(Syscall::OpenFailed {
+                dirfd: Some(4),
+                path: String::from("foo"),
+                error: "ENOENT"
+            })
+        );
+
+        assert_eq!(
+            parse("close(3) = -1 EBADF (Bad file descriptor)"),
+            Some(Syscall::Close {
+                fd: 3,
+                error: Some("EBADF")
+            })
+        );
+
+        assert_eq!(
+            parse("fcntl(3, F_DUPFD, 10) = 10"),
+            Some(Syscall::Dup {
+                oldfd: 3,
+                newfd: 10,
+                cloexec: false,
+            })
+        );
+
+        assert_eq!(parse("fcntl(3, F_GETFL) = 0x2 (flags O_RDWR)"), None);
+    }
+
+    #[test]
+    fn parse_read_write_syscalls() {
+        assert_eq!(
+            parse(r#"read(3, "x, 42) = 1"..., 4096) = 10"#),
+            Some(Syscall::Read {
+                fd: 3,
+                size: 4096,
+                bytes: 10
+            })
+        );
+
+        assert_eq!(
+            parse(
+                "read(3, 0x7ffd, 4096) = -1 EAGAIN (Resource temporarily unavailable)"
+            ),
+            Some(Syscall::ReadFailed {
+                fd: 3,
+                error: "EAGAIN"
+            })
+        );
+    }
+
+    #[test]
+    fn parse_process_syscalls() {
+        assert_eq!(
+            parse(
+                "clone(child_stack=NULL, flags=CLONE_CHILD_CLEARTID|CLONE_CHILD_SETTID|SIGCHLD, child_tidptr=0x7f) = 42"
+            ),
+            Some(Syscall::Clone {
+                child: 42,
+                shared_files: false
+            })
+        );
+
+        assert_eq!(
+            parse(
+                "clone3({flags=CLONE_VM|CLONE_FS|CLONE_FILES|CLONE_SIGHAND|CLONE_THREAD|CLONE_SYSVSEM|CLONE_SETTLS|CLONE_PARENT_SETTID|CLONE_CHILD_CLEARTID, child_tid=0x7f, parent_tid=0x7f, exit_signal=0, stack=0x7f, stack_size=0x7fff00, tls=0x7f} => {parent_tid=[43]}, 88) = 43"
+            ),
+            Some(Syscall::Clone {
+                child: 43,
+                shared_files: true
+            })
+        );
+
+        assert_eq!(
+            parse("vfork() = 44"),
+            Some(Syscall::Clone {
+                child: 44,
+                shared_files: false
+            })
+        );
+    }
+
+    #[test]
+    fn parse_exec_syscalls() {
+        assert_eq!(
+            parse(
+                r#"execve("/usr/bin/gcc", ["gcc", "-c", "foo"..., ""...], 0x7ffd /* 20 vars */) = 0"#
+            ),
+            Some(Syscall::Exec {
+                dirfd: None,
+                program: String::from("/usr/bin/gcc"),
+                args: vec![
+                    String::from("gcc"),
+                    String::from("-c"),
+                    String::from("foo..."),
+                    String::from("..."),
+                ],
+            })
+        );
+
+        assert_eq!(
+            parse(
+                r#"execveat(3, "bin/ls", ["ls"], 0x7ffd /* 20 vars */, 0) = 0"#
+            ),
+            Some(Syscall::Exec {
+                dirfd: Some(3),
+                program: String::from("bin/ls"),
+                args: vec![String::from("ls")],
+            })
+        );
+
+        assert_eq!(
+            parse(
+                r#"execve("/usr/local/bin/gcc", ["gcc"], 0x7ffd /* 20 vars */) = -1 ENOENT (No such file or directory)"#
+            ),
+            Some(Syscall::ExecFailed {
+                dirfd: None,
+                program: String::from("/usr/local/bin/gcc"),
+                error: "ENOENT",
+            })
+        );
+    }
+
+    #[test]
+    fn parse_cloexec_syscalls() {
+        assert_eq!(
+            parse("dup3(3, 10, O_CLOEXEC) = 10"),
+            Some(Syscall::Dup {
+                oldfd: 3,
+                newfd: 10,
+                cloexec: true,
+            })
+        );
+
+        assert_eq!(
+            parse("fcntl(3, F_DUPFD_CLOEXEC, 0) = 5"),
+            Some(Syscall::Dup {
+                oldfd: 3,
+                newfd: 5,
+                cloexec: true,
+            })
+        );
+
+        assert_eq!(
+            parse("fcntl(5, F_SETFD, FD_CLOEXEC) = 0"),
+            Some(Syscall::SetCloexec {
+                fd: 5,
+                cloexec: true,
+            })
+        );
+
+        assert_eq!(
+            parse("ioctl(5, FIONCLEX) = 0"),
+            Some(Syscall::SetCloexec {
+                fd: 5,
+                cloexec: false,
+            })
+        );
+
+        assert_eq!(
+            parse("pipe2([3, 4], O_CLOEXEC) = 0"),
+            Some(Syscall::Pipe {
+                read: 3,
+                write: 4,
+                cloexec: true,
+            })
+        );
+
+        assert_eq!(
+            parse("pipe2([3, 4], O_NONBLOCK) = 0"),
+            Some(Syscall::Pipe {
+                read: 3,
+                write: 4,
+                cloexec: false,
+            })
+        );
+
+        assert_eq!(
+            parse(
+                "accept4(3, {sa_family=AF_INET, sin_port=htons(80), sin_addr=inet_addr(\"10.0.0.1\")}, [16], SOCK_CLOEXEC) = 4"
+            ),
+            Some(Syscall::Accept {
+                listen: 3,
+                address: Some(
+                    "{sa_family=AF_INET, sin_port=htons(80), sin_addr=inet_addr(\"10.0.0.1\")}"
+                ),
+                fd: 4,
+                cloexec: true,
+            })
+        );
+
+        assert_eq!(
+            parse(
+                "socketpair(AF_UNIX, SOCK_STREAM|SOCK_CLOEXEC, 0, [5, 6]) = 0"
+            ),
+            Some(Syscall::SocketPair {
+                domain: "AF_UNIX",
+                kind: "SOCK_STREAM",
+                first: 5,
+                second: 6,
+                cloexec: true,
+            })
+        );
+
+        assert_eq!(
+            parse(
+                r#"openat2(AT_FDCWD, "/etc/hosts", {flags=O_RDONLY|O_CLOEXEC, resolve=0}, 24) = 3"#
+            ),
+            Some(Syscall::Open {
+                dirfd: None,
+                path: String::from("/etc/hosts"),
+                fd: 3,
+                cloexec: true,
+            })
+        );
+    }
+
+    #[test]
+    fn parse_close_range_syscalls() {
+        assert_eq!(
+            parse("close_range(3, ~0U, 0) = 0"),
+            Some(Syscall::CloseRange {
+                first: 3,
+                last: u32::MAX,
+                cloexec: false,
+                unshare: false,
+            })
+        );
+
+        assert_eq!(
+            parse("close_range(3, 4294967295, CLOSE_RANGE_UNSHARE) = 0"),
+            Some(Syscall::CloseRange {
+                first: 3,
+                last: u32::MAX,
+                cloexec: false,
+                unshare: true,
+            })
+        );
+
+        assert_eq!(
+            parse("close_range(3, 9, CLOSE_RANGE_CLOEXEC) = 0"),
+            Some(Syscall::CloseRange {
+                first: 3,
+                last: 9,
+                cloexec: true,
+                unshare: false,
+            })
+        );
+    }
+}